@@ -0,0 +1,54 @@
+// Internet2 addresses with support for Tor v3
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//     Martin Habovstiak <martin.habovstiak@gmail.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Compares [`parse_ip_fast`] with [`InetAddr::from_str`] on IP addresses.
+//! Run with `cargo bench -p inet2_addr --bench parse_ip`.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::time::Instant;
+
+use inet2_addr::{parse_ip_fast, InetAddr};
+
+fn main() {
+    let addrs = (0..100_000u32)
+        .map(|n| {
+            if n % 2 == 0 {
+                Ipv4Addr::from(n).to_string()
+            } else {
+                Ipv6Addr::from(n as u128).to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    for addr in &addrs {
+        InetAddr::from_str(addr).unwrap();
+    }
+    let from_str = start.elapsed();
+
+    let start = Instant::now();
+    for addr in &addrs {
+        parse_ip_fast(addr).unwrap();
+    }
+    let fast = start.elapsed();
+
+    println!(
+        "parsing {} addresses: from_str {:?}, parse_ip_fast {:?}",
+        addrs.len(),
+        from_str,
+        fast
+    );
+}
@@ -0,0 +1,132 @@
+// Internet2 addresses with support for Tor v3
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//     Martin Habovstiak <martin.habovstiak@gmail.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Conversions between [`InetAddr`] and bitcoin P2P network address records
+//! (BIP-155 `addrv2`) from `rust-bitcoin` library
+
+use std::convert::TryFrom;
+
+use bitcoin::network::address::AddrV2;
+#[cfg(feature = "tor")]
+use torut::onion::TorPublicKeyV3;
+
+use crate::InetAddr;
+
+/// Errors converting bitcoin P2P [`AddrV2`] address into [`InetAddr`]
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error
+)]
+#[display(doc_comments)]
+pub enum AddrV2Error {
+    /// network type of the address is not supported by internet addresses
+    UnsupportedNetwork,
+
+    /// Tor v3 address contains invalid public key
+    InvalidTorKey,
+}
+
+impl From<InetAddr> for AddrV2 {
+    #[inline]
+    fn from(addr: InetAddr) -> Self {
+        match addr {
+            InetAddr::IPv4(ip) => AddrV2::Ipv4(ip),
+            InetAddr::IPv6(ip) => AddrV2::Ipv6(ip),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(key) => AddrV2::TorV3(key.to_bytes()),
+        }
+    }
+}
+
+impl TryFrom<AddrV2> for InetAddr {
+    type Error = AddrV2Error;
+
+    fn try_from(addr: AddrV2) -> Result<Self, Self::Error> {
+        Ok(match addr {
+            AddrV2::Ipv4(ip) => InetAddr::IPv4(ip),
+            AddrV2::Ipv6(ip) => InetAddr::IPv6(ip),
+            #[cfg(feature = "tor")]
+            AddrV2::TorV3(key) => InetAddr::Tor(
+                TorPublicKeyV3::from_bytes(&key)
+                    .map_err(|_| AddrV2Error::InvalidTorKey)?,
+            ),
+            // CJDNS uses IPv6 format, but belongs to a different network, so
+            // we do not convert it into IPv6 address
+            _ => return Err(AddrV2Error::UnsupportedNetwork),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    #[cfg(feature = "tor")]
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_addr_v2_roundtrip() {
+        let ip4 = InetAddr::IPv4(Ipv4Addr::new(192, 168, 0, 1));
+        let ip6 = InetAddr::IPv6(Ipv6Addr::LOCALHOST);
+
+        assert_eq!(
+            AddrV2::from(ip4),
+            AddrV2::Ipv4(Ipv4Addr::new(192, 168, 0, 1))
+        );
+        assert_eq!(AddrV2::from(ip6), AddrV2::Ipv6(Ipv6Addr::LOCALHOST));
+        assert_eq!(InetAddr::try_from(AddrV2::from(ip4)), Ok(ip4));
+        assert_eq!(InetAddr::try_from(AddrV2::from(ip6)), Ok(ip6));
+
+        #[cfg(feature = "tor")]
+        {
+            let tor = InetAddr::from_str(
+                "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd",
+            )
+            .unwrap();
+            assert!(matches!(AddrV2::from(tor), AddrV2::TorV3(_)));
+            assert_eq!(InetAddr::try_from(AddrV2::from(tor)), Ok(tor));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tor")]
+    fn test_addr_v2_invalid_tor() {
+        // Not a valid ed25519 curve point
+        assert_eq!(
+            InetAddr::try_from(AddrV2::TorV3([2u8; 32])),
+            Err(AddrV2Error::InvalidTorKey)
+        );
+    }
+
+    #[test]
+    fn test_addr_v2_unsupported() {
+        assert_eq!(
+            InetAddr::try_from(AddrV2::TorV2([0u8; 10])),
+            Err(AddrV2Error::UnsupportedNetwork)
+        );
+        assert_eq!(
+            InetAddr::try_from(AddrV2::I2p([0u8; 32])),
+            Err(AddrV2Error::UnsupportedNetwork)
+        );
+        assert_eq!(
+            InetAddr::try_from(AddrV2::Cjdns(Ipv6Addr::LOCALHOST)),
+            Err(AddrV2Error::UnsupportedNetwork)
+        );
+        assert_eq!(
+            InetAddr::try_from(AddrV2::Unknown(0xFF, vec![])),
+            Err(AddrV2Error::UnsupportedNetwork)
+        );
+    }
+}
@@ -13,11 +13,14 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use std::io;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 use strict_encoding::net::{
-    AddrFormat, DecodeError, RawAddr, Transport, Uniform, UniformAddr, ADDR_LEN,
+    AddrFormat, DecodeError, RawAddr, RawUniformAddr, Transport, Uniform,
+    UniformAddr, ADDR_LEN,
 };
+use strict_encoding::{StrictDecode, StrictEncode};
 #[cfg(feature = "tor")]
 use torut::onion::{TorPublicKeyV3, TORV3_PUBLIC_KEY_LENGTH};
 
@@ -31,8 +34,32 @@ impl strict_encoding::Strategy for InetSocketAddr {
     type Strategy = strict_encoding::strategies::UsingUniformAddr;
 }
 
-impl strict_encoding::Strategy for InetSocketAddrExt {
-    type Strategy = strict_encoding::strategies::UsingUniformAddr;
+// Not using `UsingUniformAddr` strategy here since it can't fail on the
+// transports which have no uniform representation
+impl StrictEncode for InetSocketAddrExt {
+    fn strict_encode<E: io::Write>(
+        &self,
+        e: E,
+    ) -> Result<usize, strict_encoding::Error> {
+        if Uniform::transport(self).is_none() {
+            return Err(strict_encoding::Error::UnsupportedDataStructure(
+                "transport protocol of the socket address has no uniform \
+                 encoding",
+            ));
+        }
+        self.to_raw_uniform().strict_encode(e)
+    }
+}
+
+impl StrictDecode for InetSocketAddrExt {
+    fn strict_decode<D: io::Read>(
+        d: D,
+    ) -> Result<Self, strict_encoding::Error> {
+        let raw = RawUniformAddr::strict_decode(d)?;
+        InetSocketAddrExt::from_raw_uniform_addr(raw).map_err(|err| {
+            strict_encoding::Error::DataIntegrityError(err.to_string())
+        })
+    }
 }
 
 impl Uniform for InetAddr {
@@ -151,6 +178,9 @@ impl Uniform for InetSocketAddrExt {
             crate::Transport::Udp => Transport::Udp,
             crate::Transport::Mtcp => Transport::Mtcp,
             crate::Transport::Quic => Transport::Quic,
+            // Uniform encoding has no representation for these transports, so
+            // strict encoding of such addresses fails
+            crate::Transport::Sctp | crate::Transport::Dccp => return None,
         })
     }
 
@@ -39,15 +39,26 @@ extern crate strict_encoding;
 #[macro_use]
 extern crate serde_crate as serde;
 
+#[cfg(feature = "bitcoin")]
+mod addr_v2;
 #[cfg(feature = "strict_encoding")]
 mod encoding;
+mod ln_gossip;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+
+#[cfg(feature = "bitcoin")]
+pub use addr_v2::AddrV2Error;
+pub use ln_gossip::{ln_gossip_decode_list, LnGossipError};
 
 use std::cmp::Ordering;
-#[cfg(feature = "tor")]
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt;
+use std::io;
 use std::net::{
     IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6,
+    ToSocketAddrs,
 };
 use std::num::ParseIntError;
 use std::str::FromStr;
@@ -63,6 +74,39 @@ use torut::onion::{OnionAddressV3, TorPublicKeyV3};
 #[display(doc_comments)]
 pub struct NoOnionSupportError;
 
+/// Reasons why a string which looks like an onion address can't be parsed
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum OnionAddrError {
+    /// it has invalid length
+    InvalidLength,
+
+    /// it is not a valid base32 string
+    InvalidBase32,
+
+    /// its checksum is invalid
+    InvalidChecksum,
+
+    /// it has unknown version byte
+    InvalidVersion,
+
+    /// it is a deprecated Tor v2 address, which is not supported
+    DeprecatedV2,
+}
+
+#[cfg(feature = "tor")]
+impl From<torut::onion::OnionAddressParseError> for OnionAddrError {
+    fn from(err: torut::onion::OnionAddressParseError) -> Self {
+        use torut::onion::OnionAddressParseError as Torut;
+        match err {
+            Torut::InvalidLength => OnionAddrError::InvalidLength,
+            Torut::Base32Error => OnionAddrError::InvalidBase32,
+            Torut::InvalidChecksum => OnionAddrError::InvalidChecksum,
+            Torut::InvalidVersion => OnionAddrError::InvalidVersion,
+        }
+    }
+}
+
 /// Errors during address string parse process
 #[derive(
     Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error, From
@@ -76,12 +120,19 @@ pub enum AddrParseError {
     /// Can't recognize IPv4, v6 or Onion v2/v3 address in string "{_0}"
     WrongAddrFormat(String),
 
+    /// "{_0}" looks like an IP address, but has invalid syntax
+    InvalidIpAddr(String),
+
+    /// "{_0}" looks like an onion address, but {_1}
+    InvalidOnionAddr(String, OnionAddrError),
+
     /// Wrong format of socket address string "{_0}"; use
     /// <inet_address>\[:<port>\]
     WrongSocketFormat(String),
 
     /// Wrong format of extended socket address string "{_0}"; use
-    /// <transport>://<inet_address>\[:<port>\]
+    /// <transport>://<inet_address>\[:<port>\] with a single non-empty
+    /// transport scheme
     WrongSocketExtFormat(String),
 
     /// Unknown transport protocol "{_0}"
@@ -89,6 +140,144 @@ pub enum AddrParseError {
 
     /// Tor addresses are not supported; consider compiling with `tor` feature
     NeedsTorFeature,
+
+    /// Socket address string "{_0}" must contain port number
+    MissingPort(String),
+
+    /// Wrong length of raw address data ({_0} bytes); it must be 4 bytes for
+    /// IPv4, 16 bytes for IPv6 or 32 bytes for Tor v3 public key
+    WrongRawLength(usize),
+
+    /// Raw address data does not represent a valid Tor v3 public key
+    InvalidTorKey,
+
+    /// Address {_0} belongs to a range reserved for documentation and can't
+    /// be used by a real peer
+    ReservedRange(String),
+
+    /// Unsupported socket address family {_0}; only AF_INET and AF_INET6
+    /// are supported
+    UnsupportedFamily(i32),
+}
+
+impl AddrParseError {
+    /// Returns human-readable explanation of the error with a suggestion on
+    /// how it can be fixed, suitable for presenting to end users (for
+    /// instance, in command-line tools)
+    pub fn explain(&self) -> String {
+        match self {
+            AddrParseError::WrongPortNumber => s!(
+                "the port must be a number between 0 and 65535; check that \
+                 the part of the address after the last colon is a valid \
+                 port number"
+            ),
+            AddrParseError::WrongAddrFormat(addr) => format!(
+                "\"{}\" is not an IP or onion address; domain names are not \
+                 resolved, so use a numeric IPv4 (like 127.0.0.1), IPv6 (like \
+                 ::1) or Tor v3 onion address instead",
+                addr
+            ),
+            AddrParseError::InvalidIpAddr(addr) => format!(
+                "\"{}\" is not a valid IP address; check that IPv4 address \
+                 has four numbers between 0 and 255 separated by dots and \
+                 that IPv6 address has no more than eight groups of hex \
+                 digits",
+                addr
+            ),
+            AddrParseError::InvalidOnionAddr(addr, err) => match err {
+                OnionAddrError::DeprecatedV2 => format!(
+                    "\"{}\" is a Tor v2 onion address, which is no longer \
+                     supported by Tor network; use Tor v3 address of the \
+                     service instead",
+                    addr
+                ),
+                err => format!(
+                    "\"{}\" looks like an onion address, but {}; check that \
+                     the address was not mistyped or truncated",
+                    addr, err
+                ),
+            },
+            AddrParseError::WrongSocketFormat(addr) => format!(
+                "\"{}\" is not a valid socket address; use \
+                 `<address>:<port>` form and enclose IPv6 addresses in square \
+                 brackets, like [::1]:9735",
+                addr
+            ),
+            AddrParseError::WrongSocketExtFormat(addr) => format!(
+                "\"{}\" is not a valid extended socket address; use \
+                 `<transport>://<address>:<port>` form with a single \
+                 transport scheme, like tcp://127.0.0.1:9735",
+                addr
+            ),
+            AddrParseError::UnknownProtocolError(proto) => format!(
+                "\"{}\" is not a known transport protocol; use one of tcp, \
+                 udp, mtcp, quic, sctp or dccp",
+                proto
+            ),
+            AddrParseError::NeedsTorFeature => s!(
+                "this looks like a Tor address, but Tor support is not \
+                 compiled in; rebuild with the `tor` feature to use onion \
+                 addresses"
+            ),
+            AddrParseError::MissingPort(addr) => format!(
+                "\"{}\" does not specify a port; append it after a colon, \
+                 like {}:9735",
+                addr, addr
+            ),
+            AddrParseError::WrongRawLength(len) => format!(
+                "raw address data of {} bytes can't be interpreted as an \
+                 address; provide 4 bytes for IPv4, 16 bytes for IPv6 or 32 \
+                 bytes for a Tor v3 public key",
+                len
+            ),
+            AddrParseError::UnsupportedFamily(family) => format!(
+                "socket address of family {} can't be used as an internet \
+                 address; provide an IPv4 (AF_INET) or IPv6 (AF_INET6) \
+                 socket address",
+                family
+            ),
+            AddrParseError::InvalidTorKey => s!(
+                "the data are not a valid Tor v3 public key; check that the \
+                 onion address was not corrupted or truncated"
+            ),
+            AddrParseError::ReservedRange(addr) => format!(
+                "\"{}\" is an example address reserved for documentation, \
+                 which can't belong to a real peer; check that the \
+                 configuration does not contain a copy-pasted example",
+                addr
+            ),
+        }
+    }
+}
+
+/// Length of the base32-encoded part of Tor v3 onion address (without `.onion`
+/// suffix)
+const ONION_V3_BASE32_LEN: usize = 56;
+
+/// Length of the base32-encoded part of Tor v2 onion address (without `.onion`
+/// suffix)
+const ONION_V2_BASE32_LEN: usize = 16;
+
+/// Port conventionally used by Lightning network nodes, which is assumed for
+/// Tor onion addresses without port by
+/// [`InetSocketAddr::from_str_default_port`]
+pub const ONION_DEFAULT_PORT: u16 = 9735;
+
+/// Detects whether a given string looks like a Tor onion address, i.e. is a
+/// base32 string of Tor v2 or v3 address length followed by `.onion` suffix.
+///
+/// The function does not validate onion address checksum and works
+/// independently from the `tor` feature, so it can be used for detecting
+/// onion addresses in builds which are not able to parse them.
+pub fn looks_like_onion(s: &str) -> bool {
+    let s = s.to_ascii_lowercase();
+    let base32 = match s.strip_suffix(".onion") {
+        Some(base32) => base32,
+        None => return false,
+    };
+    let len = base32.len();
+    (len == ONION_V3_BASE32_LEN || len == ONION_V2_BASE32_LEN)
+        && base32.chars().all(|c| matches!(c, 'a'..='z' | '2'..='7'))
 }
 
 /// A universal address covering IPv4, IPv6 and Tor in a single byte sequence
@@ -106,8 +295,9 @@ pub enum AddrParseError {
 /// address was typed in correctly. In computer-stored digital data it may be
 /// deterministically regenerated and does not add any additional security.
 ///
-/// Tor addresses are distinguished by the fact that last 16 bits
-/// must be set to 0
+/// In the uniform encoding (see [`InetAddr::to_uniform_encoding`]) IP
+/// addresses occupy the last 16 bytes, and Tor addresses are distinguished
+/// by the fact that the first 16 bytes are not all set to 0
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[cfg_attr(
     all(feature = "serde", feature = "serde_str_helpers"),
@@ -149,12 +339,13 @@ impl PartialOrd for InetAddr {
             (InetAddr::Tor(addr1), InetAddr::Tor(addr2)) => {
                 addr1.partial_cmp(addr2)
             }
+            // IPv4 > IPv6 > Tor regardless of the enabled features
             (InetAddr::IPv4(_), _) => Some(Ordering::Greater),
             (_, InetAddr::IPv4(_)) => Some(Ordering::Less),
             #[cfg(feature = "tor")]
-            (InetAddr::IPv6(_), _) => Some(Ordering::Greater),
+            (InetAddr::IPv6(_), InetAddr::Tor(_)) => Some(Ordering::Greater),
             #[cfg(feature = "tor")]
-            (_, InetAddr::IPv6(_)) => Some(Ordering::Less),
+            (InetAddr::Tor(_), InetAddr::IPv6(_)) => Some(Ordering::Less),
         }
     }
 }
@@ -179,6 +370,22 @@ impl std::hash::Hash for InetAddr {
 }
 
 impl InetAddr {
+    /// Constructs IPv4 address from its octets. Unlike `From<[u8; 4]>`
+    /// implementation, can be used in `const` context.
+    #[inline]
+    pub const fn from_ipv4_octets(octets: [u8; 4]) -> Self {
+        let [a, b, c, d] = octets;
+        InetAddr::IPv4(Ipv4Addr::new(a, b, c, d))
+    }
+
+    /// Constructs IPv6 address from its 16-bit segments. Unlike
+    /// `From<[u16; 8]>` implementation, can be used in `const` context.
+    #[inline]
+    pub const fn from_ipv6_segments(segments: [u16; 8]) -> Self {
+        let [a, b, c, d, e, f, g, h] = segments;
+        InetAddr::IPv6(Ipv6Addr::new(a, b, c, d, e, f, g, h))
+    }
+
     /// Returns an IPv6 address, constructed from IPv4 data; or, if Onion
     /// address is used, [`Option::None`]
     #[inline]
@@ -191,17 +398,277 @@ impl InetAddr {
         }
     }
 
-    /// Returns an IPv4 address, if any, or [`Option::None`]
+    /// Returns an IPv4 address, including the one extracted from IPv4-mapped
+    /// IPv6 address (`::ffff:a.b.c.d`); or [`Option::None`] for other IPv6
+    /// and Onion addresses
+    #[inline]
+    pub fn to_ipv4(&self) -> Option<Ipv4Addr> {
+        match self.normalized() {
+            InetAddr::IPv4(ipv4_addr) => Some(ipv4_addr),
+            _ => None,
+        }
+    }
+
+    /// Returns length in bytes of the canonical binary representation of the
+    /// address family, as used in LN gossip `net_address` encoding: 4 bytes
+    /// for IPv4, 16 for IPv6 and 32 for Tor v3 public key. Tor v2 addresses
+    /// are not supported by the library.
     #[inline]
-    pub fn to_ipv4(&self) -> Option<Ipv6Addr> {
+    pub fn family_byte_len(&self) -> usize {
         match self {
-            InetAddr::IPv4(ipv4_addr) => Some(ipv4_addr.to_ipv6_mapped()),
-            InetAddr::IPv6(ipv6_addr) => Some(*ipv6_addr),
+            InetAddr::IPv4(_) => 4,
+            InetAddr::IPv6(_) => 16,
             #[cfg(feature = "tor")]
-            _ => None,
+            InetAddr::Tor(_) => 32,
+        }
+    }
+
+    /// Returns 32-byte uniform encoding of the address. IP addresses are
+    /// encoded as 16 zero bytes followed by IPv6 address (IPv4 addresses are
+    /// IPv4-mapped); Tor addresses are encoded as a 32-byte public key. The
+    /// same layout is used for the address part of
+    /// [`InetSocketAddrExt::to_compact_bytes`].
+    pub fn to_uniform_encoding(&self) -> [u8; 32] {
+        let mut data = [0u8; 32];
+        match self {
+            InetAddr::IPv4(ip) => {
+                data[16..].copy_from_slice(&ip.to_ipv6_mapped().octets())
+            }
+            InetAddr::IPv6(ip) => data[16..].copy_from_slice(&ip.octets()),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(key) => data.copy_from_slice(&key.to_bytes()),
+        }
+        data
+    }
+
+    /// Decodes address from its 32-byte uniform encoding produced by
+    /// [`InetAddr::to_uniform_encoding`]. IPv4-mapped IPv6 addresses are
+    /// decoded as IPv4 addresses.
+    ///
+    /// Returns `None` if the first 16 bytes are not all zeros (i.e. the data
+    /// must represent a Tor address) and they are not a valid Tor public key,
+    /// or if the library is compiled without `tor` feature. Thus the data
+    /// received from untrusted peers never produces an invalid Tor key.
+    ///
+    /// NB: all-zero data is decoded as unspecified IPv6 address (`::`) and
+    /// not as a Tor key; valid Tor keys starting with 16 zero bytes can't be
+    /// represented by the uniform encoding.
+    pub fn from_uniform_encoding(data: [u8; 32]) -> Option<Self> {
+        if data[..16] == [0u8; 16] {
+            let mut ip = [0u8; 16];
+            ip.copy_from_slice(&data[16..]);
+            return Some(InetAddr::IPv6(Ipv6Addr::from(ip)).normalized());
+        }
+        #[cfg(feature = "tor")]
+        return TorPublicKeyV3::from_bytes(&data).ok().map(InetAddr::Tor);
+        #[cfg(not(feature = "tor"))]
+        None
+    }
+
+    /// Returns normalized form of the address, converting IPv4-mapped IPv6
+    /// addresses (like `::ffff:1.2.3.4`) into IPv4 addresses. Other addresses
+    /// are returned unchanged.
+    #[inline]
+    pub fn normalized(self) -> Self {
+        match self {
+            InetAddr::IPv6(ip) => {
+                let octets = ip.octets();
+                if octets[..12] == [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF] {
+                    InetAddr::IPv4(Ipv4Addr::new(
+                        octets[12], octets[13], octets[14], octets[15],
+                    ))
+                } else {
+                    self
+                }
+            }
+            addr => addr,
         }
     }
 
+    /// Determines whether the address belongs to the IPv4 shared address
+    /// space (`100.64.0.0/10`, RFC 6598) used by carrier-grade NAT. Such
+    /// addresses are not reachable from the public internet, but are not
+    /// covered by [`Ipv4Addr::is_private`]. IPv4-mapped IPv6 addresses are
+    /// checked as IPv4 addresses.
+    #[inline]
+    pub fn is_shared_address_space(&self) -> bool {
+        match self.normalized() {
+            InetAddr::IPv4(ip) => {
+                let octets = ip.octets();
+                octets[0] == 100 && octets[1] & 0xC0 == 64
+            }
+            _ => false,
+        }
+    }
+
+    /// Determines whether the address is a loopback address (`127.0.0.0/8`
+    /// or `::1`), as defined by [`Ipv4Addr::is_loopback`] and
+    /// [`Ipv6Addr::is_loopback`]. IPv4-mapped IPv6 addresses are checked as
+    /// IPv4 addresses. Tor addresses are never loopback.
+    #[inline]
+    pub fn is_loopback(&self) -> bool {
+        match self.normalized() {
+            InetAddr::IPv4(ip) => ip.is_loopback(),
+            InetAddr::IPv6(ip) => ip.is_loopback(),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => false,
+        }
+    }
+
+    /// Determines whether the address is an unspecified address (`0.0.0.0`
+    /// or `::`), as defined by [`Ipv4Addr::is_unspecified`] and
+    /// [`Ipv6Addr::is_unspecified`]. IPv4-mapped IPv6 addresses are checked
+    /// as IPv4 addresses. Tor addresses are never unspecified.
+    #[inline]
+    pub fn is_unspecified(&self) -> bool {
+        match self.normalized() {
+            InetAddr::IPv4(ip) => ip.is_unspecified(),
+            InetAddr::IPv6(ip) => ip.is_unspecified(),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => false,
+        }
+    }
+
+    /// Determines whether the address is a multicast address (`224.0.0.0/4`
+    /// or `ff00::/8`), as defined by [`Ipv4Addr::is_multicast`] and
+    /// [`Ipv6Addr::is_multicast`]. IPv4-mapped IPv6 addresses are checked as
+    /// IPv4 addresses. Tor addresses are never multicast.
+    #[inline]
+    pub fn is_multicast(&self) -> bool {
+        match self.normalized() {
+            InetAddr::IPv4(ip) => ip.is_multicast(),
+            InetAddr::IPv6(ip) => ip.is_multicast(),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => false,
+        }
+    }
+
+    /// Determines whether the address belongs to private network ranges:
+    /// `10.0.0.0/8`, `172.16.0.0/12` and `192.168.0.0/16` for IPv4 (see
+    /// [`Ipv4Addr::is_private`]) and unique local `fc00::/7` range for IPv6
+    /// (RFC 4193). IPv4-mapped IPv6 addresses are checked as IPv4 addresses.
+    /// Tor addresses are never private in the IP sense, even though they may
+    /// belong to hidden services which are not publicly announced.
+    #[inline]
+    pub fn is_private(&self) -> bool {
+        match self.normalized() {
+            InetAddr::IPv4(ip) => ip.is_private(),
+            InetAddr::IPv6(ip) => ip.segments()[0] & 0xFE00 == 0xFC00,
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => false,
+        }
+    }
+
+    /// Determines whether the address belongs to the ranges reserved for
+    /// documentation and examples: `192.0.2.0/24`, `198.51.100.0/24`,
+    /// `203.0.113.0/24` (RFC 5737) and `2001:db8::/32` (RFC 3849). Such
+    /// addresses must never be used by real peers. IPv4-mapped IPv6
+    /// addresses are checked as IPv4 addresses.
+    pub fn is_documentation(&self) -> bool {
+        match self.normalized() {
+            InetAddr::IPv4(ip) => ip.is_documentation(),
+            InetAddr::IPv6(ip) => ip.segments()[..2] == [0x2001, 0xDB8],
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => false,
+        }
+    }
+
+    /// Determines whether the address is globally reachable, i.e. does not
+    /// belong to private, loopback, link-local, shared (CGNAT),
+    /// documentation, benchmarking, multicast or other special-use ranges.
+    /// IPv4-mapped IPv6 addresses are checked as IPv4 addresses. Tor
+    /// addresses are always considered global, since they are reachable
+    /// from anywhere via Tor network.
+    pub fn is_global(&self) -> bool {
+        match self.normalized() {
+            InetAddr::IPv4(ip) => {
+                let octets = ip.octets();
+                !(octets[0] == 0
+                    || ip.is_private()
+                    || ip.is_loopback()
+                    || ip.is_link_local()
+                    || ip.is_broadcast()
+                    || ip.is_multicast()
+                    || ip.is_documentation()
+                    || self.is_shared_address_space()
+                    // IETF protocol assignments, 192.0.0.0/24
+                    || octets[..3] == [192, 0, 0]
+                    // Benchmarking, 198.18.0.0/15
+                    || octets[0] == 198 && octets[1] & 0xFE == 18
+                    // Reserved, 240.0.0.0/4
+                    || octets[0] >= 240)
+            }
+            InetAddr::IPv6(ip) => {
+                let segments = ip.segments();
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    // Unique local, fc00::/7
+                    || segments[0] & 0xFE00 == 0xFC00
+                    // Link-local unicast, fe80::/10
+                    || segments[0] & 0xFFC0 == 0xFE80
+                    // Documentation, 2001:db8::/32
+                    || segments[..2] == [0x2001, 0xDB8]
+                    // Discard-only, 100::/64
+                    || segments[..4] == [0x100, 0, 0, 0])
+            }
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => true,
+        }
+    }
+
+    /// Checks whether two strings are differently written forms of the same
+    /// address, like IPv6 addresses with and without zero compression or
+    /// onion addresses in a different letter case. Strings which are not
+    /// valid addresses are compared as is.
+    ///
+    /// Useful for deduplication of addresses coming from string sources.
+    pub fn same_addr_str(a: &str, b: &str) -> bool {
+        match (InetAddr::from_str(a), InetAddr::from_str(b)) {
+            // Parsing normalizes the address forms
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        }
+    }
+
+    /// Returns host representation of the address suitable for URIs and
+    /// socket address strings, where IPv6 addresses are enclosed in square
+    /// brackets (RFC 3986), like `[::1]`. IPv4 and onion addresses are
+    /// returned in their [`fmt::Display`] form.
+    pub fn to_uri_host(&self) -> String {
+        match self {
+            InetAddr::IPv6(ip) => format!("[{}]", ip),
+            addr => addr.to_string(),
+        }
+    }
+
+    /// Parses address string like [`InetAddr::from_str`], but additionally
+    /// rejects addresses from documentation ranges (see
+    /// [`InetAddr::is_documentation`]) with [`AddrParseError::ReservedRange`].
+    /// Intended for parsing addresses of remote peers, for which such
+    /// addresses indicate misconfiguration.
+    pub fn from_str_reject_reserved(s: &str) -> Result<Self, AddrParseError> {
+        let addr = InetAddr::from_str(s)?;
+        if addr.is_documentation() {
+            return Err(AddrParseError::ReservedRange(s.to_owned()));
+        }
+        Ok(addr)
+    }
+
+    /// Returns single-host network for the IP address (`/32` network for
+    /// IPv4 and `/128` for IPv6), or [`Option::None`] for Tor addresses
+    #[cfg(feature = "ipnetwork")]
+    #[inline]
+    pub fn as_host_network(&self) -> Option<ipnetwork::IpNetwork> {
+        let (ip, prefix) = match self {
+            InetAddr::IPv4(ip) => (IpAddr::V4(*ip), 32),
+            InetAddr::IPv6(ip) => (IpAddr::V6(*ip), 128),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => return None,
+        };
+        ipnetwork::IpNetwork::new(ip, prefix).ok()
+    }
+
     /// Determines whether provided address is a Tor address. Always returns
     /// `fales` (the library is built without `tor` feature; use it to
     /// enable Tor addresses).
@@ -248,7 +715,9 @@ impl fmt::Display for InetAddr {
             InetAddr::IPv4(addr) => write!(f, "{}", addr),
             InetAddr::IPv6(addr) => write!(f, "{}", addr),
             #[cfg(feature = "tor")]
-            InetAddr::Tor(addr) => write!(f, "{}", addr),
+            InetAddr::Tor(addr) => {
+                write!(f, "{}", OnionAddressV3::from(addr))
+            }
         }
     }
 }
@@ -317,27 +786,148 @@ impl_try_from_stringly_standard!(InetAddr);
 #[cfg(feature = "stringly_conversions")]
 impl_into_stringly_standard!(InetAddr);
 
+/// Parses IPv4 and IPv6 addresses in their standard notation and (with `tor`
+/// feature) Tor v3 onion addresses, which may be given either with `.onion`
+/// suffix (the form produced by [`fmt::Display`]) or as a bare 56-character
+/// base32 string. Since the suffix and base32 alphabet can't be a part of an
+/// IP address, the forms do not overlap.
 impl FromStr for InetAddr {
     type Err = AddrParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         #[cfg(feature = "tor")]
-        match (IpAddr::from_str(s), OnionAddressV3::from_str(s)) {
+        let onion = s.strip_suffix(".onion").unwrap_or(s);
+        #[cfg(feature = "tor")]
+        match (IpAddr::from_str(s), OnionAddressV3::from_str(onion)) {
             (Ok(_), Ok(_)) => {
                 Err(AddrParseError::WrongAddrFormat(s.to_owned()))
             }
             (Ok(ip_addr), _) => Ok(Self::from(ip_addr)),
             (_, Ok(onionv3)) => Ok(Self::from(onionv3)),
-            _ => Err(AddrParseError::WrongAddrFormat(s.to_owned())),
+            _ => Err(addr_parse_failure(s)),
         }
 
         #[cfg(not(feature = "tor"))]
         match IpAddr::from_str(s) {
             Ok(ip_addr) => Ok(InetAddr::from(ip_addr)),
-            _ => Err(AddrParseError::NeedsTorFeature),
+            _ if looks_like_onion(s) => Err(AddrParseError::NeedsTorFeature),
+            _ => Err(addr_parse_failure(s)),
         }
     }
 }
 
+/// Detects which address family the string which failed to parse was
+/// intended for, returning the most specific error
+fn addr_parse_failure(s: &str) -> AddrParseError {
+    let ipv4_like = s.contains('.')
+        && s.chars().all(|c| c.is_ascii_digit() || c == '.');
+    let ipv6_like = s.contains(':')
+        && s.chars().all(|c| c.is_ascii_hexdigit() || c == ':' || c == '.');
+    if ipv4_like || ipv6_like {
+        return AddrParseError::InvalidIpAddr(s.to_owned());
+    }
+
+    let lower = s.to_ascii_lowercase();
+    let base32 = lower.strip_suffix(".onion").unwrap_or(&lower);
+    if !base32.chars().all(|c| matches!(c, 'a'..='z' | '2'..='7')) {
+        return AddrParseError::WrongAddrFormat(s.to_owned());
+    }
+    match base32.len() {
+        ONION_V2_BASE32_LEN => AddrParseError::InvalidOnionAddr(
+            s.to_owned(),
+            OnionAddrError::DeprecatedV2,
+        ),
+        #[cfg(feature = "tor")]
+        ONION_V3_BASE32_LEN => match OnionAddressV3::from_str(base32) {
+            Err(err) => {
+                AddrParseError::InvalidOnionAddr(s.to_owned(), err.into())
+            }
+            // Valid onion address in the form rejected by the parser, like
+            // upper-case one
+            Ok(_) => AddrParseError::WrongAddrFormat(s.to_owned()),
+        },
+        _ => AddrParseError::WrongAddrFormat(s.to_owned()),
+    }
+}
+
+/// Parses IPv4 or IPv6 address string without attempting to parse it as an
+/// onion address, which makes it faster than [`InetAddr::from_str`] for the
+/// bulk parsing of address lists known to contain IP addresses only.
+///
+/// # Errors
+/// [`AddrParseError::WrongAddrFormat`] for any string which is not an IP
+/// address, including valid onion addresses.
+#[inline]
+pub fn parse_ip_fast(s: &str) -> Result<InetAddr, AddrParseError> {
+    IpAddr::from_str(s)
+        .map(InetAddr::from)
+        .map_err(|_| AddrParseError::WrongAddrFormat(s.to_owned()))
+}
+
+/// Internet address parsed from a string, which keeps the original string
+/// representation alongside with the parsed address. Unlike [`InetAddr`],
+/// which is always displayed in canonical form (i.e. `::1` for
+/// `0:0:0:0:0:0:0:1`), the address is displayed and serialized exactly as it
+/// was read, allowing byte-identical round-tripping of configuration files.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display("{original}")]
+pub struct ParsedAddr {
+    addr: InetAddr,
+    original: String,
+}
+
+impl ParsedAddr {
+    /// Returns parsed address
+    #[inline]
+    pub fn addr(&self) -> InetAddr { self.addr }
+
+    /// Returns the original string from which the address was parsed
+    #[inline]
+    pub fn original(&self) -> &str { &self.original }
+}
+
+impl From<ParsedAddr> for InetAddr {
+    #[inline]
+    fn from(parsed: ParsedAddr) -> Self { parsed.addr }
+}
+
+#[cfg(feature = "stringly_conversions")]
+impl_try_from_stringly_standard!(ParsedAddr);
+#[cfg(feature = "stringly_conversions")]
+impl_into_stringly_standard!(ParsedAddr);
+
+impl FromStr for ParsedAddr {
+    type Err = AddrParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(ParsedAddr {
+            addr: InetAddr::from_str(s)?,
+            original: s.to_owned(),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ParsedAddr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.original)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ParsedAddr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        ParsedAddr::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 // Yes, I checked that onion addresses don't need to optimize ownership of input
 // String.
 #[cfg(feature = "parse_arg")]
@@ -356,7 +946,7 @@ impl parse_arg::ParseArgFromStr for InetAddr {
 
 impl From<[u8; 4]> for InetAddr {
     #[inline]
-    fn from(value: [u8; 4]) -> Self { InetAddr::from(Ipv4Addr::from(value)) }
+    fn from(value: [u8; 4]) -> Self { InetAddr::from_ipv4_octets(value) }
 }
 
 impl From<[u8; 16]> for InetAddr {
@@ -366,7 +956,40 @@ impl From<[u8; 16]> for InetAddr {
 
 impl From<[u16; 8]> for InetAddr {
     #[inline]
-    fn from(value: [u16; 8]) -> Self { InetAddr::from(Ipv6Addr::from(value)) }
+    fn from(value: [u16; 8]) -> Self { InetAddr::from_ipv6_segments(value) }
+}
+
+/// Parses raw address data, detecting address family from the data length:
+/// 4 bytes are read as IPv4 address, 16 bytes as IPv6 address and 32 bytes
+/// as Tor v3 public key (which requires `tor` feature).
+impl TryFrom<&[u8]> for InetAddr {
+    type Error = AddrParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        match value.len() {
+            4 => {
+                let mut buf = [0u8; 4];
+                buf.copy_from_slice(value);
+                Ok(InetAddr::from(buf))
+            }
+            16 => {
+                let mut buf = [0u8; 16];
+                buf.copy_from_slice(value);
+                Ok(InetAddr::from(buf))
+            }
+            #[cfg(feature = "tor")]
+            32 => {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(value);
+                TorPublicKeyV3::from_bytes(&buf)
+                    .map(InetAddr::Tor)
+                    .map_err(|_| AddrParseError::InvalidTorKey)
+            }
+            #[cfg(not(feature = "tor"))]
+            32 => Err(AddrParseError::NeedsTorFeature),
+            len => Err(AddrParseError::WrongRawLength(len)),
+        }
+    }
 }
 
 /// Transport protocols that may be part of [`InetSocketAddrExt`]
@@ -395,13 +1018,20 @@ pub enum Transport {
     /// other internet companies
     #[display("quic")]
     Quic = 4,
+
+    /// Stream Control Transmission Protocol, used by telephony signalling
+    /// stacks
+    #[display("sctp")]
+    Sctp = 5,
+
+    /// Datagram Congestion Control Protocol
+    #[display("dccp")]
+    Dccp = 6,
     /* There are other rarely used protocols. Do not see any reason to add
      * them to the crate for now, but it may appear in the future,
      * so keeping them for referencing purposes: */
     /*
     UdpLite,
-    Sctp,
-    Dccp,
     Rudp,
     */
 }
@@ -411,6 +1041,119 @@ impl Default for Transport {
     fn default() -> Self { Transport::Tcp }
 }
 
+impl Transport {
+    /// Transport protocols ordered by their preference, from the most
+    /// preferred one, used by [`Transport::negotiate`]
+    pub const PREFERENCE_ORDER: [Transport; 4] = [
+        Transport::Quic,
+        Transport::Mtcp,
+        Transport::Tcp,
+        Transport::Udp,
+    ];
+
+    /// Selects the most preferred transport protocol supported by both local
+    /// and remote peers. The preference order is QUIC > MTCP > TCP > UDP
+    /// (see [`Transport::PREFERENCE_ORDER`]); other transport protocols are
+    /// never selected.
+    ///
+    /// Returns `None` if the peers have no transport protocols in common.
+    pub fn negotiate(
+        local: &[Transport],
+        remote: &[Transport],
+    ) -> Option<Transport> {
+        Transport::PREFERENCE_ORDER.iter().copied().find(|transport| {
+            local.contains(transport) && remote.contains(transport)
+        })
+    }
+
+    /// Returns port conventionally used by Lightning network nodes with the
+    /// transport protocol: [`ONION_DEFAULT_PORT`] (9735) for TCP and
+    /// multipath TCP, which falls back to TCP. Returns `None` for other
+    /// transports, which have no well-known port for Lightning network nodes.
+    #[inline]
+    pub fn default_port(&self) -> Option<u16> {
+        match self {
+            Transport::Tcp | Transport::Mtcp => Some(ONION_DEFAULT_PORT),
+            Transport::Udp
+            | Transport::Quic
+            | Transport::Sctp
+            | Transport::Dccp => None,
+        }
+    }
+
+    /// Returns URL scheme name of the transport protocol, matching its
+    /// [`fmt::Display`] representation
+    #[inline]
+    pub fn standard_scheme(&self) -> &'static str {
+        match self {
+            Transport::Tcp => "tcp",
+            Transport::Udp => "udp",
+            Transport::Mtcp => "mtcp",
+            Transport::Quic => "quic",
+            Transport::Sctp => "sctp",
+            Transport::Dccp => "dccp",
+        }
+    }
+
+    /// Detects whether the transport protocol establishes a connection
+    /// between peers before any data can be sent. This is the case for all
+    /// supported protocols except UDP.
+    ///
+    /// NB: QUIC is connection-oriented even though it runs on top of UDP;
+    /// DCCP is connection-oriented but not [reliable](Self::is_reliable).
+    #[inline]
+    pub fn is_connection_oriented(&self) -> bool {
+        match self {
+            Transport::Tcp
+            | Transport::Mtcp
+            | Transport::Quic
+            | Transport::Sctp
+            | Transport::Dccp => true,
+            Transport::Udp => false,
+        }
+    }
+
+    /// Detects whether the transport protocol guarantees delivery of the
+    /// data, so the session layer does not have to handle lost messages.
+    ///
+    /// NB: QUIC is reliable for its streams, which are used by this library,
+    /// while it may also carry unreliable datagrams (RFC 9221); the latter
+    /// are not used for the session data. UDP and DCCP are unreliable.
+    #[inline]
+    pub fn is_reliable(&self) -> bool {
+        match self {
+            Transport::Tcp
+            | Transport::Mtcp
+            | Transport::Quic
+            | Transport::Sctp => true,
+            Transport::Udp | Transport::Dccp => false,
+        }
+    }
+}
+
+impl TryFrom<u8> for Transport {
+    type Error = AddrParseError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => Transport::Tcp,
+            2 => Transport::Udp,
+            3 => Transport::Mtcp,
+            4 => Transport::Quic,
+            5 => Transport::Sctp,
+            6 => Transport::Dccp,
+            _ => {
+                return Err(AddrParseError::UnknownProtocolError(
+                    value.to_string(),
+                ))
+            }
+        })
+    }
+}
+
+/// Parses transport protocol from its name or, for compatibility with
+/// configurations storing the protocol as a number, from the decimal
+/// representation of its discriminant
 impl FromStr for Transport {
     type Err = AddrParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -419,6 +1162,12 @@ impl FromStr for Transport {
             "udp" => Transport::Udp,
             "mtcp" => Transport::Mtcp,
             "quic" => Transport::Quic,
+            "sctp" => Transport::Sctp,
+            "dccp" => Transport::Dccp,
+            num if num.bytes().all(|b| b.is_ascii_digit()) => num
+                .parse::<u8>()
+                .map_err(|_| AddrParseError::UnknownProtocolError(s.to_owned()))
+                .and_then(Transport::try_from)?,
             _ => {
                 return Err(AddrParseError::UnknownProtocolError(s.to_owned()))
             }
@@ -460,27 +1209,186 @@ impl_into_stringly_standard!(InetSocketAddr);
 
 impl InetSocketAddr {
     /// Constructs new socket address from an internet address and a port
-    /// information
+    /// information. Can be used in `const` context.
     #[inline]
-    pub fn new(address: InetAddr, port: u16) -> Self { Self { address, port } }
+    pub const fn new(address: InetAddr, port: u16) -> Self {
+        Self { address, port }
+    }
 
     /// Determines whether provided address is a Tor address
     #[inline]
     pub fn is_tor(&self) -> bool { self.address.is_tor() }
-}
 
-impl fmt::Display for InetSocketAddr {
+    /// Splits socket address into host string and port number. The host is
+    /// returned in [`InetAddr::to_uri_host`] form, so IPv6 addresses are
+    /// enclosed in square brackets and the parts can be recombined with
+    /// `format!("{}:{}", host, port)`.
     #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{}", self.address, self.port)
+    pub fn into_host_port(self) -> (String, u16) {
+        (self.address.to_uri_host(), self.port)
     }
-}
 
-impl FromStr for InetSocketAddr {
-    type Err = AddrParseError;
+    /// Determines whether the socket address has zero port, i.e. requests
+    /// operating system to assign an ephemeral port when used for binding
+    #[inline]
+    pub fn is_ephemeral(&self) -> bool { self.port == 0 }
 
-    #[allow(unreachable_code)]
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    /// Determines whether the socket address has unspecified IP address
+    /// (`0.0.0.0` or `::`), i.e. listens on all network interfaces. Tor
+    /// addresses are never wildcards.
+    #[inline]
+    pub fn is_wildcard(&self) -> bool {
+        match self.address {
+            InetAddr::IPv4(ip) => ip.is_unspecified(),
+            InetAddr::IPv6(ip) => ip.is_unspecified(),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => false,
+        }
+    }
+
+    /// Checks the address against peer admission policy given by the lists
+    /// of allowed and denied networks, with denial overriding allowance. An
+    /// empty `allow` list permits all addresses which are not denied.
+    /// IPv4-mapped IPv6 addresses are matched as IPv4 addresses. Tor
+    /// addresses, which do not belong to any IP network, are permitted only if
+    /// `allow_tor` is set.
+    #[cfg(feature = "ipnetwork")]
+    pub fn is_permitted(
+        &self,
+        allow: &[ipnetwork::IpNetwork],
+        deny: &[ipnetwork::IpNetwork],
+        allow_tor: bool,
+    ) -> bool {
+        let ip = match self.address.normalized() {
+            InetAddr::IPv4(ip) => IpAddr::V4(ip),
+            InetAddr::IPv6(ip) => IpAddr::V6(ip),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => return allow_tor,
+        };
+        !deny.iter().any(|network| network.contains(ip))
+            && (allow.is_empty()
+                || allow.iter().any(|network| network.contains(ip)))
+    }
+
+    /// Returns socket address with normalized internet address, as defined by
+    /// [`InetAddr::normalized`]
+    #[inline]
+    pub fn normalized(self) -> Self {
+        Self::new(self.address.normalized(), self.port)
+    }
+
+    /// Converts IPv6 socket address into [`InetSocketAddr`], returning IPv6
+    /// `flowinfo` and `scope_id` values alongside (in this order), which are
+    /// dropped by the `From<SocketAddrV6>` conversion.
+    #[inline]
+    pub fn from_socket_addr_v6(addr: SocketAddrV6) -> (Self, u32, u32) {
+        (addr.into(), addr.flowinfo(), addr.scope_id())
+    }
+
+    /// Constructs IPv6 socket address with the provided `flowinfo` and
+    /// `scope_id` values. IPv4 addresses are converted into IPv4-mapped IPv6
+    /// form; for Tor addresses returns [`Option::None`].
+    #[inline]
+    pub fn to_socket_addr_v6(
+        &self,
+        flowinfo: u32,
+        scope_id: u32,
+    ) -> Option<SocketAddrV6> {
+        self.address
+            .to_ipv6()
+            .map(|ip| SocketAddrV6::new(ip, self.port, flowinfo, scope_id))
+    }
+
+    /// Constructs socket address from the `sockaddr_storage` structure filled
+    /// by system networking APIs, interpreting `AF_INET` and `AF_INET6`
+    /// address families.
+    ///
+    /// # Errors
+    /// - [`AddrParseError::UnsupportedFamily`] for other address families;
+    /// - [`AddrParseError::WrongRawLength`] if `len` is less than the size of
+    ///   the socket address structure for the address family.
+    ///
+    /// # Safety
+    /// `storage` must be initialized by a system call (or manually) such that
+    /// its first `len` bytes hold a valid socket address of the family given
+    /// by the `ss_family` field.
+    #[cfg(all(feature = "ffi", unix))]
+    pub unsafe fn from_sockaddr(
+        storage: &libc::sockaddr_storage,
+        len: libc::socklen_t,
+    ) -> Result<Self, AddrParseError> {
+        use std::mem::size_of;
+
+        let len = len as usize;
+        match i32::from(storage.ss_family) {
+            libc::AF_INET if len >= size_of::<libc::sockaddr_in>() => {
+                let sin = &*(storage as *const libc::sockaddr_storage
+                    as *const libc::sockaddr_in);
+                let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+                Ok(Self::new(ip.into(), u16::from_be(sin.sin_port)))
+            }
+            libc::AF_INET6 if len >= size_of::<libc::sockaddr_in6>() => {
+                let sin6 = &*(storage as *const libc::sockaddr_storage
+                    as *const libc::sockaddr_in6);
+                let ip = Ipv6Addr::from(sin6.sin6_addr.s6_addr);
+                Ok(Self::new(ip.into(), u16::from_be(sin6.sin6_port)))
+            }
+            libc::AF_INET | libc::AF_INET6 => {
+                Err(AddrParseError::WrongRawLength(len))
+            }
+            family => Err(AddrParseError::UnsupportedFamily(family)),
+        }
+    }
+
+    /// Parses socket address string which must contain port number.
+    ///
+    /// Unlike [`InetSocketAddr::from_str`], which assumes port 0 when the port
+    /// is absent, this function fails with [`AddrParseError::MissingPort`] in
+    /// such case. Explicit `:0` port (used by listeners to request an
+    /// ephemeral port) is still accepted.
+    #[inline]
+    pub fn from_str_port_required(s: &str) -> Result<Self, AddrParseError> {
+        Self::parse(s, true)
+    }
+
+    /// Parses socket address string, using `default_port` when the port is
+    /// absent.
+    ///
+    /// NB: Tor onion addresses without port always get
+    /// [`ONION_DEFAULT_PORT`] (9735) instead of `default_port`, since onion
+    /// addresses are mostly used by Lightning nodes and are commonly shared
+    /// without port. Explicitly provided ports are never overridden.
+    pub fn from_str_default_port(
+        s: &str,
+        default_port: u16,
+    ) -> Result<Self, AddrParseError> {
+        match Self::from_str_port_required(s) {
+            Err(AddrParseError::MissingPort(_)) => {
+                let address = InetAddr::from_str(s)?;
+                let port = if address.is_tor() {
+                    ONION_DEFAULT_PORT
+                } else {
+                    default_port
+                };
+                Ok(Self::new(address, port))
+            }
+            res => res,
+        }
+    }
+
+    /// Parses socket address string like [`InetSocketAddr::from_str`], but
+    /// rejects addresses from documentation ranges with
+    /// [`AddrParseError::ReservedRange`]. See
+    /// [`InetAddr::from_str_reject_reserved`] for the details.
+    pub fn from_str_reject_reserved(s: &str) -> Result<Self, AddrParseError> {
+        let addr = InetSocketAddr::from_str(s)?;
+        if addr.address.is_documentation() {
+            return Err(AddrParseError::ReservedRange(s.to_owned()));
+        }
+        Ok(addr)
+    }
+
+    fn parse(s: &str, port_required: bool) -> Result<Self, AddrParseError> {
         if let Ok(socket_addr) = SocketAddrV6::from_str(s) {
             return Ok(Self::new(
                 (*socket_addr.ip()).into(),
@@ -491,19 +1399,27 @@ impl FromStr for InetSocketAddr {
                 (*socket_addr.ip()).into(),
                 socket_addr.port(),
             ));
-        } else {
-            #[cfg(not(feature = "tor"))]
-            return Err(AddrParseError::NeedsTorFeature);
+        } else if let Ok(ip) = Ipv6Addr::from_str(s) {
+            // Bare IPv6 address without port contains colons, so it must be
+            // detected before splitting `<address>:<port>` string
+            return if port_required {
+                Err(AddrParseError::MissingPort(s.to_owned()))
+            } else {
+                Ok(Self::new(ip.into(), 0))
+            };
         }
 
         let mut vals = s.split(':');
         match (vals.next(), vals.next(), vals.next()) {
             (Some(addr), Some(port), None) => Ok(Self {
-                address: addr.parse()?,
+                address: InetAddr::from_str(addr)?,
                 port: u16::from_str(port)?,
             }),
+            (Some(_), None, _) if port_required => {
+                Err(AddrParseError::MissingPort(s.to_owned()))
+            }
             (Some(addr), None, _) => Ok(Self {
-                address: addr.parse()?,
+                address: InetAddr::from_str(addr)?,
                 port: 0,
             }),
             _ => Err(AddrParseError::WrongSocketFormat(s.to_owned())),
@@ -511,23 +1427,58 @@ impl FromStr for InetSocketAddr {
     }
 }
 
-#[cfg(feature = "tor")]
-impl TryFrom<InetSocketAddr> for SocketAddr {
-    type Error = NoOnionSupportError;
-    #[inline]
-    fn try_from(socket_addr: InetSocketAddr) -> Result<Self, Self::Error> {
-        Ok(Self::new(
-            IpAddr::try_from(socket_addr.address)?,
-            socket_addr.port,
-        ))
+/// Normalizes list of socket addresses by converting IPv4-mapped IPv6
+/// addresses (like `::ffff:1.2.3.4`) into IPv4 form and removes duplicated
+/// addresses, preserving the order in which the addresses were first seen.
+pub fn dedup_normalized(addrs: &mut Vec<InetSocketAddr>) {
+    for addr in addrs.iter_mut() {
+        *addr = addr.normalized();
     }
+    let mut seen = HashSet::with_capacity(addrs.len());
+    addrs.retain(|addr| seen.insert(*addr));
 }
 
-#[cfg(not(feature = "tor"))]
-impl From<InetSocketAddr> for SocketAddr {
+impl fmt::Display for InetSocketAddr {
     #[inline]
-    fn from(socket_addr: InetSocketAddr) -> Self {
-        Self::new(IpAddr::from(socket_addr.address), socket_addr.port)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address {
+            InetAddr::IPv6(_) => write!(f, "[{}]:{}", self.address, self.port),
+            _ => write!(f, "{}:{}", self.address, self.port),
+        }
+    }
+}
+
+impl FromStr for InetSocketAddr {
+    type Err = AddrParseError;
+
+    /// Parses socket address string in `<inet_address>[:<port>]` format.
+    ///
+    /// NB: if the port is absent, it is assumed to be 0, so `0.0.0.0` and
+    /// `0.0.0.0:0` are parsed into the same value. Since for listeners port 0
+    /// means "use an ephemeral port", consider using
+    /// [`InetSocketAddr::from_str_port_required`] when the port must be
+    /// provided explicitly.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::parse(s, false) }
+}
+
+#[cfg(feature = "tor")]
+impl TryFrom<InetSocketAddr> for SocketAddr {
+    type Error = NoOnionSupportError;
+    #[inline]
+    fn try_from(socket_addr: InetSocketAddr) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            IpAddr::try_from(socket_addr.address)?,
+            socket_addr.port,
+        ))
+    }
+}
+
+#[cfg(not(feature = "tor"))]
+impl From<InetSocketAddr> for SocketAddr {
+    #[inline]
+    fn from(socket_addr: InetSocketAddr) -> Self {
+        Self::new(IpAddr::from(socket_addr.address), socket_addr.port)
     }
 }
 
@@ -545,6 +1496,32 @@ impl From<SocketAddrV4> for InetSocketAddr {
     }
 }
 
+/// Allows to use socket address directly with [`std::net::TcpStream::connect`]
+/// and similar functions. Tor addresses can't be resolved into IP socket
+/// addresses and fail with [`io::ErrorKind::Unsupported`] error.
+impl ToSocketAddrs for InetSocketAddr {
+    type Iter = std::vec::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        let ip = match self.address {
+            InetAddr::IPv4(ip) => IpAddr::V4(ip),
+            InetAddr::IPv6(ip) => IpAddr::V6(ip),
+            #[cfg(feature = "tor")]
+            InetAddr::Tor(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    NoOnionSupportError,
+                ))
+            }
+        };
+        Ok(vec![SocketAddr::new(ip, self.port)].into_iter())
+    }
+}
+
+/// NB: the conversion is lossy, since IPv6 `flowinfo` and `scope_id` are not
+/// represented by [`InetSocketAddr`] and are dropped. Use
+/// [`InetSocketAddr::from_socket_addr_v6`] to retrieve them alongside the
+/// address.
 impl From<SocketAddrV6> for InetSocketAddr {
     #[inline]
     fn from(addr: SocketAddrV6) -> Self {
@@ -586,16 +1563,90 @@ impl InetSocketAddrExt {
     /// Constructs [`InetSocketAddrExt`] for a given internet address and TCP
     /// port
     #[inline]
-    pub fn tcp(address: InetAddr, port: u16) -> Self {
+    pub const fn tcp(address: InetAddr, port: u16) -> Self {
         Self(Transport::Tcp, InetSocketAddr::new(address, port))
     }
 
     /// Constructs [`InetSocketAddrExt`] for a given internet address and UDP
     /// port
     #[inline]
-    pub fn udp(address: InetAddr, port: u16) -> Self {
+    pub const fn udp(address: InetAddr, port: u16) -> Self {
         Self(Transport::Udp, InetSocketAddr::new(address, port))
     }
+
+    /// Constructs [`InetSocketAddrExt`] for a given transport protocol and
+    /// socket address
+    #[inline]
+    pub const fn with(transport: Transport, socket: InetSocketAddr) -> Self {
+        Self(transport, socket)
+    }
+
+    /// Returns transport-level protocol of the address
+    #[inline]
+    pub const fn transport(&self) -> Transport { self.0 }
+
+    /// Returns socket address (internet address and port) without
+    /// transport-level protocol information
+    #[inline]
+    pub const fn socket_addr(&self) -> InetSocketAddr { self.1 }
+
+    /// Constructs [`InetSocketAddrExt`] for a given transport protocol and
+    /// internet address using the default port of the transport (see
+    /// [`Transport::default_port`]), or returns `None` if the transport has
+    /// no default port
+    #[inline]
+    pub fn with_default_port(
+        transport: Transport,
+        address: InetAddr,
+    ) -> Option<Self> {
+        let port = transport.default_port()?;
+        Some(Self(transport, InetSocketAddr::new(address, port)))
+    }
+
+    /// Length of the compact binary representation produced by
+    /// [`InetSocketAddrExt::to_compact_bytes`]
+    pub const COMPACT_LEN: usize = 1 + 32 + 2;
+
+    /// Serializes the address into a compact binary form: a single transport
+    /// byte followed by 32-byte address and 2-byte big-endian port number.
+    ///
+    /// IP addresses are stored in IPv6 form (IPv4 addresses being mapped to
+    /// IPv6) in the last 16 address bytes, while the first 16 bytes are
+    /// zeroed; Tor v3 addresses occupy all 32 bytes with the public key.
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::COMPACT_LEN);
+        buf.push(self.0 as u8);
+        buf.extend_from_slice(&self.1.address.to_uniform_encoding());
+        buf.extend_from_slice(&self.1.port.to_be_bytes());
+        buf
+    }
+
+    /// Deserializes the address from the compact binary form produced by
+    /// [`InetSocketAddrExt::to_compact_bytes`]. IPv4-mapped IPv6 addresses
+    /// are always decoded as IPv4 addresses.
+    ///
+    /// # Errors
+    /// - [`AddrParseError::WrongRawLength`] if the data are not exactly
+    ///   [`InetSocketAddrExt::COMPACT_LEN`] bytes long;
+    /// - [`AddrParseError::UnknownProtocolError`] for unknown transport byte;
+    /// - [`AddrParseError::InvalidTorKey`] if the address is not an IP address
+    ///   and does not represent a valid Tor v3 public key;
+    /// - [`AddrParseError::NeedsTorFeature`] for Tor addresses when the crate
+    ///   is compiled without `tor` feature.
+    pub fn from_compact_bytes(data: &[u8]) -> Result<Self, AddrParseError> {
+        if data.len() != Self::COMPACT_LEN {
+            return Err(AddrParseError::WrongRawLength(data.len()));
+        }
+        let transport = Transport::try_from(data[0])?;
+        let raw = &data[1..33];
+        let address = if raw[..16].iter().all(|b| *b == 0) {
+            InetAddr::try_from(&raw[16..])?.normalized()
+        } else {
+            InetAddr::try_from(raw)?
+        };
+        let port = u16::from_be_bytes([data[33], data[34]]);
+        Ok(Self(transport, InetSocketAddr::new(address, port)))
+    }
 }
 
 impl fmt::Display for InetSocketAddrExt {
@@ -608,13 +1659,17 @@ impl fmt::Display for InetSocketAddrExt {
 impl FromStr for InetSocketAddrExt {
     type Err = AddrParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Neither IP nor onion addresses may contain `://`, so the split is
+        // unambiguous and any other number of parts means nested or missed
+        // scheme
         let mut vals = s.split("://");
-        if let (Some(transport), Some(addr), None) =
-            (vals.next(), vals.next(), vals.next())
-        {
-            Ok(Self(transport.parse()?, addr.parse()?))
-        } else {
-            Err(AddrParseError::WrongSocketExtFormat(s.to_owned()))
+        match (vals.next(), vals.next(), vals.next()) {
+            (Some(transport), Some(addr), None)
+                if !transport.is_empty() && !addr.is_empty() =>
+            {
+                Ok(Self(transport.parse()?, addr.parse()?))
+            }
+            _ => Err(AddrParseError::WrongSocketExtFormat(s.to_owned())),
         }
     }
 }
@@ -623,7 +1678,58 @@ impl FromStr for InetSocketAddrExt {
 mod test {
     use super::*;
 
-    // TODO: Add tests for Tor
+    #[cfg(feature = "tor")]
+    const SAMPLE_ONION: &str =
+        "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd";
+
+    /// Returns known-valid Tor v3 address
+    #[cfg(feature = "tor")]
+    fn sample_onion() -> InetAddr { InetAddr::from_str(SAMPLE_ONION).unwrap() }
+
+    #[test]
+    #[cfg(feature = "tor")]
+    fn test_inet_addr_tor() {
+        let onion = sample_onion();
+        let key = OnionAddressV3::from_str(SAMPLE_ONION)
+            .unwrap()
+            .get_public_key();
+        assert_eq!(onion, InetAddr::Tor(key));
+        assert_eq!(format!("{}", onion), format!("{}.onion", SAMPLE_ONION));
+        assert_eq!(
+            onion.to_onion().unwrap().get_address_without_dot_onion(),
+            SAMPLE_ONION
+        );
+        assert!(onion.is_tor());
+        assert_eq!(onion.to_ipv6(), None);
+        assert_eq!(IpAddr::try_from(onion), Err(NoOnionSupportError));
+        assert_eq!(InetAddr::from(key), onion);
+        assert_eq!(
+            InetAddr::from_str(&format!("{}.onion", SAMPLE_ONION)),
+            Ok(onion)
+        );
+        assert_eq!(InetAddr::from_str(&onion.to_string()), Ok(onion));
+        assert_eq!(
+            InetAddr::from_str(&format!("{}.onion.onion", SAMPLE_ONION)),
+            Err(AddrParseError::WrongAddrFormat(format!(
+                "{}.onion.onion",
+                SAMPLE_ONION
+            )))
+        );
+
+        let ip4 = InetAddr::from(Ipv4Addr::LOCALHOST);
+        let ip6 = InetAddr::from(Ipv6Addr::LOCALHOST);
+        assert!(ip4 > onion);
+        assert!(ip6 > onion);
+        assert_eq!(onion.cmp(&sample_onion()), Ordering::Equal);
+
+        let socket = InetSocketAddr::new(onion, 9735);
+        assert!(socket.is_tor());
+        assert_eq!(
+            InetSocketAddr::from_str(&format!("{}:9735", SAMPLE_ONION)),
+            Ok(socket)
+        );
+        assert_eq!(SocketAddr::try_from(socket), Err(NoOnionSupportError));
+    }
 
     #[test]
     fn test_inet_addr() {
@@ -663,12 +1769,642 @@ mod test {
         assert!(!ip6.is_tor());
     }
 
+    #[test]
+    fn test_inet_addr_from_slice() {
+        assert_eq!(
+            InetAddr::try_from(&[127u8, 0, 0, 1][..]),
+            Ok(InetAddr::IPv4(Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(
+            InetAddr::try_from(&Ipv6Addr::LOCALHOST.octets()[..]),
+            Ok(InetAddr::IPv6(Ipv6Addr::LOCALHOST))
+        );
+        assert_eq!(
+            InetAddr::try_from(&[0u8; 5][..]),
+            Err(AddrParseError::WrongRawLength(5))
+        );
+        assert_eq!(
+            InetAddr::try_from(&[][..]),
+            Err(AddrParseError::WrongRawLength(0))
+        );
+
+        #[cfg(feature = "tor")]
+        {
+            let onion = sample_onion();
+            let key = match onion {
+                InetAddr::Tor(key) => key.to_bytes(),
+                _ => unreachable!(),
+            };
+            assert_eq!(InetAddr::try_from(&key[..]), Ok(onion));
+            assert_eq!(
+                InetAddr::try_from(&[2u8; 32][..]),
+                Err(AddrParseError::InvalidTorKey)
+            );
+        }
+        #[cfg(not(feature = "tor"))]
+        assert_eq!(
+            InetAddr::try_from(&[0u8; 32][..]),
+            Err(AddrParseError::NeedsTorFeature)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ipnetwork")]
+    fn test_as_host_network() {
+        let ip4 = InetAddr::from([192, 168, 0, 1]);
+        let network = ip4.as_host_network().unwrap();
+        assert_eq!(network.prefix(), 32);
+        assert_eq!(network.ip(), IpAddr::from([192, 168, 0, 1]));
+        assert_eq!(network.to_string(), "192.168.0.1/32");
+
+        let ip6 = InetAddr::from(Ipv6Addr::LOCALHOST);
+        let network = ip6.as_host_network().unwrap();
+        assert_eq!(network.prefix(), 128);
+        assert_eq!(network.ip(), IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert_eq!(network.to_string(), "::1/128");
+
+        #[cfg(feature = "tor")]
+        assert_eq!(sample_onion().as_host_network(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "ipnetwork")]
+    fn test_is_permitted() {
+        use ipnetwork::IpNetwork;
+
+        let net = |s: &str| IpNetwork::from_str(s).unwrap();
+        let addr = |s: &str| InetSocketAddr::from_str(s).unwrap();
+        let lan = [net("192.168.0.0/16"), net("fd00::/8")];
+
+        // Allow-only
+        assert!(addr("192.168.1.1:9735").is_permitted(&lan, &[], false));
+        assert!(addr("[fd00::1]:9735").is_permitted(&lan, &[], false));
+        assert!(!addr("10.0.0.1:9735").is_permitted(&lan, &[], false));
+        assert!(addr("10.0.0.1:9735").is_permitted(&[], &[], false));
+        assert!(
+            addr("[::ffff:192.168.1.1]:9735").is_permitted(&lan, &[], false)
+        );
+
+        // Deny overrides allow
+        let deny = [net("192.168.1.0/24")];
+        assert!(!addr("192.168.1.1:9735").is_permitted(&lan, &deny, false));
+        assert!(addr("192.168.2.1:9735").is_permitted(&lan, &deny, false));
+        assert!(!addr("192.168.1.1:9735").is_permitted(&[], &deny, false));
+
+        // Tor flag
+        #[cfg(feature = "tor")]
+        {
+            let onion = InetSocketAddr::new(sample_onion(), 9735);
+            assert!(!onion.is_permitted(&[], &[], false));
+            assert!(onion.is_permitted(&[], &[], true));
+            assert!(onion.is_permitted(&lan, &deny, true));
+        }
+    }
+
+    #[test]
+    fn test_parse_ip_fast() {
+        assert_eq!(
+            parse_ip_fast("127.0.0.1").unwrap(),
+            InetAddr::from(Ipv4Addr::LOCALHOST)
+        );
+        assert_eq!(
+            parse_ip_fast("::1").unwrap(),
+            InetAddr::from(Ipv6Addr::LOCALHOST)
+        );
+        let onion =
+            "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd.onion";
+        assert_eq!(
+            parse_ip_fast(onion),
+            Err(AddrParseError::WrongAddrFormat(onion.to_owned()))
+        );
+        assert_eq!(
+            parse_ip_fast("some.host"),
+            Err(AddrParseError::WrongAddrFormat(s!("some.host")))
+        );
+    }
+
+    #[test]
+    fn test_is_global() {
+        let addr = |s: &str| InetAddr::from_str(s).unwrap();
+
+        assert!(addr("100.64.0.1").is_shared_address_space());
+        assert!(addr("100.127.255.255").is_shared_address_space());
+        assert!(addr("::ffff:100.64.0.1").is_shared_address_space());
+        assert!(!addr("100.128.0.1").is_shared_address_space());
+        assert!(!addr("100.63.255.255").is_shared_address_space());
+        assert!(!addr("::1").is_shared_address_space());
+
+        for non_global in [
+            "100.64.0.1",
+            "192.0.2.1",
+            "198.51.100.1",
+            "198.18.0.1",
+            "10.0.0.1",
+            "127.0.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::ffff:192.168.1.1",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "2001:db8::1",
+        ] {
+            assert!(!addr(non_global).is_global(), "{}", non_global);
+        }
+        for global in ["1.1.1.1", "100.128.0.1", "::ffff:8.8.8.8", "2606::1"] {
+            assert!(addr(global).is_global(), "{}", global);
+        }
+        #[cfg(feature = "tor")]
+        assert!(sample_onion().is_global());
+    }
+
+    #[test]
+    fn test_parsed_addr() {
+        let parsed = ParsedAddr::from_str("0:0:0:0:0:0:0:1").unwrap();
+        assert_eq!(parsed.addr(), InetAddr::from_str("::1").unwrap());
+        assert_eq!(parsed.original(), "0:0:0:0:0:0:0:1");
+        assert_eq!(parsed.to_string(), "0:0:0:0:0:0:0:1");
+        assert_eq!(parsed.addr().to_string(), "::1");
+        assert_eq!(InetAddr::from(parsed), InetAddr::from(Ipv6Addr::LOCALHOST));
+
+        assert_ne!(
+            ParsedAddr::from_str("::1").unwrap(),
+            ParsedAddr::from_str("0::1").unwrap()
+        );
+        assert_eq!(
+            ParsedAddr::from_str("some.host"),
+            Err(AddrParseError::WrongAddrFormat(s!("some.host")))
+        );
+
+        #[cfg(feature = "serde")]
+        {
+            let parsed = ParsedAddr::from_str("::0001").unwrap();
+            let json = serde_json::to_string(&parsed).unwrap();
+            assert_eq!(json, "\"::0001\"");
+            assert_eq!(
+                serde_json::from_str::<ParsedAddr>(&json).unwrap(),
+                parsed
+            );
+        }
+    }
+
+    #[test]
+    fn test_family_byte_len() {
+        let ip4 = InetAddr::from_str("127.0.0.1").unwrap();
+        let ip6 = InetAddr::from_str("::1").unwrap();
+        assert_eq!(ip4.family_byte_len(), 4);
+        assert_eq!(ip6.family_byte_len(), 16);
+        #[cfg(feature = "tor")]
+        assert_eq!(
+            InetAddr::from_str(
+                "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd"
+            )
+            .unwrap()
+            .family_byte_len(),
+            32
+        );
+    }
+
+    #[test]
+    fn test_socket_addr_v6_ext() {
+        let ip = Ipv6Addr::from_str("fe80::1").unwrap();
+        let sa = SocketAddrV6::new(ip, 9735, 0x000A_BCDE, 3);
+
+        // Default conversion drops flowinfo and scope id
+        let inet = InetSocketAddr::from(sa);
+        assert_eq!(
+            inet.to_socket_addr_v6(0, 0),
+            Some(SocketAddrV6::new(ip, 9735, 0, 0))
+        );
+
+        let (inet_ext, flowinfo, scope_id) =
+            InetSocketAddr::from_socket_addr_v6(sa);
+        assert_eq!(inet_ext, inet);
+        assert_eq!((flowinfo, scope_id), (0x000A_BCDE, 3));
+        assert_eq!(inet.to_socket_addr_v6(flowinfo, scope_id), Some(sa));
+
+        let ipv4 = InetSocketAddr::from_str("127.0.0.1:9735").unwrap();
+        assert_eq!(
+            ipv4.to_socket_addr_v6(1, 0),
+            Some(SocketAddrV6::new(
+                Ipv4Addr::LOCALHOST.to_ipv6_mapped(),
+                9735,
+                1,
+                0
+            ))
+        );
+        #[cfg(feature = "tor")]
+        assert_eq!(
+            InetSocketAddr::new(sample_onion(), 9735).to_socket_addr_v6(1, 1),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "ffi", unix))]
+    fn test_from_sockaddr() {
+        use std::mem::{size_of, zeroed};
+
+        let socklen = |len: usize| len as libc::socklen_t;
+
+        let mut storage: libc::sockaddr_storage = unsafe { zeroed() };
+        let sin = unsafe {
+            &mut *(&mut storage as *mut libc::sockaddr_storage
+                as *mut libc::sockaddr_in)
+        };
+        sin.sin_family = libc::AF_INET as libc::sa_family_t;
+        sin.sin_port = 9735u16.to_be();
+        sin.sin_addr.s_addr = u32::from(Ipv4Addr::new(10, 0, 0, 1)).to_be();
+        let len = socklen(size_of::<libc::sockaddr_in>());
+        assert_eq!(
+            unsafe { InetSocketAddr::from_sockaddr(&storage, len) },
+            Ok(InetSocketAddr::from_str("10.0.0.1:9735").unwrap())
+        );
+        assert_eq!(
+            unsafe { InetSocketAddr::from_sockaddr(&storage, len - 1) },
+            Err(AddrParseError::WrongRawLength(len as usize - 1))
+        );
+
+        let mut storage: libc::sockaddr_storage = unsafe { zeroed() };
+        let sin6 = unsafe {
+            &mut *(&mut storage as *mut libc::sockaddr_storage
+                as *mut libc::sockaddr_in6)
+        };
+        sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+        sin6.sin6_port = 9735u16.to_be();
+        sin6.sin6_addr.s6_addr = Ipv6Addr::LOCALHOST.octets();
+        let len = socklen(size_of::<libc::sockaddr_in6>());
+        assert_eq!(
+            unsafe { InetSocketAddr::from_sockaddr(&storage, len) },
+            Ok(InetSocketAddr::from_str("[::1]:9735").unwrap())
+        );
+
+        storage.ss_family = libc::AF_UNIX as libc::sa_family_t;
+        assert_eq!(
+            unsafe { InetSocketAddr::from_sockaddr(&storage, len) },
+            Err(AddrParseError::UnsupportedFamily(libc::AF_UNIX))
+        );
+    }
+
+    #[test]
+    fn test_dedup_normalized() {
+        let mut addrs = [
+            "1.2.3.4:9735",
+            "[::ffff:5.6.7.8]:9735",
+            "[::ffff:1.2.3.4]:9735",
+            "[::1]:9735",
+            "5.6.7.8:9735",
+            "1.2.3.4:9736",
+            "[::1]:9735",
+            "1.2.3.4:9735",
+        ]
+        .iter()
+        .map(|s| InetSocketAddr::from_str(s).unwrap())
+        .collect::<Vec<_>>();
+        dedup_normalized(&mut addrs);
+        assert_eq!(
+            addrs,
+            ["1.2.3.4:9735", "5.6.7.8:9735", "[::1]:9735", "1.2.3.4:9736"]
+                .iter()
+                .map(|s| InetSocketAddr::from_str(s).unwrap())
+                .collect::<Vec<_>>()
+        );
+
+        // IPv4-compatible (not mapped) addresses must not be converted
+        let mut addrs =
+            vec![InetSocketAddr::from_str("[::1.2.3.4]:1").unwrap()];
+        dedup_normalized(&mut addrs);
+        assert!(matches!(addrs[0].address, InetAddr::IPv6(_)));
+    }
+
+    #[test]
+    fn test_inet_socket_addr_no_port() {
+        let zero = InetSocketAddr::default();
+        assert_eq!(InetSocketAddr::from_str("0.0.0.0").unwrap(), zero);
+        assert_eq!(InetSocketAddr::from_str("0.0.0.0:0").unwrap(), zero);
+        assert_eq!(
+            InetSocketAddr::from_str_port_required("0.0.0.0"),
+            Err(AddrParseError::MissingPort(s!("0.0.0.0")))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_port_required("0.0.0.0:0").unwrap(),
+            zero
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_port_required("127.0.0.1:6865").unwrap(),
+            InetSocketAddr::new(Ipv4Addr::LOCALHOST.into(), 6865)
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_port_required("[::1]:6865").unwrap(),
+            InetSocketAddr::new(Ipv6Addr::LOCALHOST.into(), 6865)
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_port_required("0.0.0.0:x"),
+            Err(AddrParseError::WrongPortNumber)
+        );
+    }
+
+    #[test]
+    fn test_to_socket_addrs() {
+        use std::net::{TcpListener, TcpStream};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = InetSocketAddr::from(listener.local_addr().unwrap());
+        assert_eq!(
+            addr.to_socket_addrs().unwrap().collect::<Vec<_>>(),
+            vec![listener.local_addr().unwrap()]
+        );
+        let stream = TcpStream::connect(addr).unwrap();
+        let (_, peer) = listener.accept().unwrap();
+        assert_eq!(peer, stream.local_addr().unwrap());
+
+        let ipv6 = InetSocketAddr::new(Ipv6Addr::LOCALHOST.into(), 6865);
+        assert_eq!(
+            ipv6.to_socket_addrs().unwrap().next(),
+            Some("[::1]:6865".parse().unwrap())
+        );
+
+        #[cfg(feature = "tor")]
+        assert_eq!(
+            InetSocketAddr::new(sample_onion(), 9735)
+                .to_socket_addrs()
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn test_inet_socket_addr_bare_ipv6() {
+        let localhost = InetAddr::from(Ipv6Addr::LOCALHOST);
+        let link_local = InetAddr::from_str("fe80::1").unwrap();
+        assert_eq!(
+            InetSocketAddr::from_str("::1"),
+            Ok(InetSocketAddr::new(localhost, 0))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str("fe80::1"),
+            Ok(InetSocketAddr::new(link_local, 0))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str("[::1]:6865"),
+            Ok(InetSocketAddr::new(localhost, 6865))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str("127.0.0.1:80"),
+            Ok(InetSocketAddr::new(Ipv4Addr::LOCALHOST.into(), 80))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_port_required("fe80::1"),
+            Err(AddrParseError::MissingPort(s!("fe80::1")))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_default_port("fe80::1", 9735),
+            Ok(InetSocketAddr::new(link_local, 9735))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str("fe80::1:x"),
+            Err(AddrParseError::WrongSocketFormat(s!("fe80::1:x")))
+        );
+    }
+
+    #[test]
+    fn test_ephemeral_wildcard() {
+        let any = InetSocketAddr::from_str("0.0.0.0:0").unwrap();
+        assert!(any.is_ephemeral());
+        assert!(any.is_wildcard());
+
+        let any_http = InetSocketAddr::from_str("0.0.0.0:80").unwrap();
+        assert!(!any_http.is_ephemeral());
+        assert!(any_http.is_wildcard());
+
+        let host = InetSocketAddr::from_str("1.2.3.4:0").unwrap();
+        assert!(host.is_ephemeral());
+        assert!(!host.is_wildcard());
+
+        let any_v6 = InetSocketAddr::from_str("[::]:9735").unwrap();
+        assert!(!any_v6.is_ephemeral());
+        assert!(any_v6.is_wildcard());
+        assert!(!InetSocketAddr::from_str("[::1]:0").unwrap().is_wildcard());
+    }
+
+    #[test]
+    fn test_looks_like_onion() {
+        assert!(looks_like_onion(
+            "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd.onion"
+        ));
+        assert!(looks_like_onion(
+            "P53LF57QOVYUVWSC6XNRPPYPLY3VTQM7L6PCOBKMYQSIOFYEZNFU5UQD.ONION"
+        ));
+        assert!(looks_like_onion("expyuzz4wqqyqhjn.onion"));
+        assert!(!looks_like_onion(
+            "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd"
+        ));
+        assert!(!looks_like_onion("p53lf57qovyuvwsc.onion.onion"));
+        assert!(!looks_like_onion("expyuzz4wqqyqhj1.onion"));
+        assert!(!looks_like_onion("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            AddrParseError::NeedsTorFeature.explain(),
+            "this looks like a Tor address, but Tor support is not compiled \
+             in; rebuild with the `tor` feature to use onion addresses"
+        );
+        assert_eq!(
+            AddrParseError::MissingPort(s!("127.0.0.1")).explain(),
+            "\"127.0.0.1\" does not specify a port; append it after a colon, \
+             like 127.0.0.1:9735"
+        );
+        assert_eq!(
+            AddrParseError::UnknownProtocolError(s!("xyz")).explain(),
+            "\"xyz\" is not a known transport protocol; use one of tcp, \
+             udp, mtcp, quic, sctp or dccp"
+        );
+        assert!(AddrParseError::WrongRawLength(5)
+            .explain()
+            .starts_with("raw address data of 5 bytes"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "tor"))]
+    fn test_inet_addr_no_tor() {
+        assert_eq!(
+            InetAddr::from_str(
+                "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd.onion"
+            ),
+            Err(AddrParseError::NeedsTorFeature)
+        );
+        assert_eq!(
+            InetAddr::from_str("some.host"),
+            Err(AddrParseError::WrongAddrFormat(s!("some.host")))
+        );
+    }
+
+    #[test]
+    fn test_addr_parse_failure() {
+        for s in ["1.2.3.256", "1.2.3", "::1::", "fe80::12345"] {
+            assert_eq!(
+                InetAddr::from_str(s),
+                Err(AddrParseError::InvalidIpAddr(s.to_owned()))
+            );
+        }
+        assert_eq!(
+            InetAddr::from_str("expyuzz4wqqyqhjn"),
+            Err(AddrParseError::InvalidOnionAddr(
+                s!("expyuzz4wqqyqhjn"),
+                OnionAddrError::DeprecatedV2
+            ))
+        );
+        assert_eq!(
+            InetAddr::from_str("local-host"),
+            Err(AddrParseError::WrongAddrFormat(s!("local-host")))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "tor")]
+    fn test_corrupted_onion() {
+        // Single character typo breaks the checksum
+        let corrupted = SAMPLE_ONION.replacen('p', "q", 1);
+        assert_eq!(
+            InetAddr::from_str(&corrupted),
+            Err(AddrParseError::InvalidOnionAddr(
+                corrupted.clone(),
+                OnionAddrError::InvalidChecksum
+            ))
+        );
+        let corrupted = format!("{}.onion", corrupted);
+        assert_eq!(
+            InetSocketAddr::from_str(&format!("{}:9735", corrupted)),
+            Err(AddrParseError::InvalidOnionAddr(
+                corrupted,
+                OnionAddrError::InvalidChecksum
+            ))
+        );
+        assert!(AddrParseError::InvalidOnionAddr(
+            SAMPLE_ONION.to_owned(),
+            OnionAddrError::InvalidChecksum
+        )
+        .explain()
+        .ends_with("check that the address was not mistyped or truncated"));
+    }
+
+    #[test]
+    fn test_to_ipv4() {
+        let ipv4 = Ipv4Addr::new(127, 0, 0, 1);
+        assert_eq!(InetAddr::IPv4(ipv4).to_ipv4(), Some(ipv4));
+        assert_eq!(InetAddr::IPv6(ipv4.to_ipv6_mapped()).to_ipv4(), Some(ipv4));
+        assert_eq!(InetAddr::IPv6(Ipv6Addr::LOCALHOST).to_ipv4(), None);
+        assert_eq!(
+            InetAddr::from_str("2001:db8::1").unwrap().to_ipv4(),
+            None
+        );
+        #[cfg(feature = "tor")]
+        assert_eq!(sample_onion().to_ipv4(), None);
+    }
+
+    #[test]
+    fn test_variant_ordering() {
+        let ipv4 = InetAddr::from(Ipv4Addr::UNSPECIFIED);
+        let ipv6 = InetAddr::from(Ipv6Addr::from([0xFF; 16]));
+        assert!(ipv4 > ipv6);
+        assert!(ipv6 < ipv4);
+        assert_eq!(ipv4.cmp(&ipv6), Ordering::Greater);
+        assert_eq!(ipv6.cmp(&ipv4), Ordering::Less);
+
+        #[cfg(feature = "tor")]
+        {
+            assert!(ipv4 > sample_onion());
+            assert!(ipv6 > sample_onion());
+            assert_eq!(sample_onion().cmp(&ipv6), Ordering::Less);
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        let addr = |s: &str| InetAddr::from_str(s).unwrap();
+
+        assert!(addr("127.0.0.1").is_loopback());
+        assert!(addr("::1").is_loopback());
+        assert!(addr("::ffff:127.0.0.1").is_loopback());
+        assert!(!addr("10.0.0.1").is_loopback());
+
+        assert!(addr("0.0.0.0").is_unspecified());
+        assert!(addr("::").is_unspecified());
+        assert!(!addr("127.0.0.1").is_unspecified());
+
+        assert!(addr("10.0.0.1").is_private());
+        assert!(addr("192.168.1.1").is_private());
+        assert!(addr("fd00::1").is_private());
+        assert!(!addr("127.0.0.1").is_private());
+        assert!(!addr("::1").is_private());
+        assert!(!addr("8.8.8.8").is_private());
+
+        assert!(addr("224.0.0.1").is_multicast());
+        assert!(addr("ff02::1").is_multicast());
+        assert!(!addr("10.0.0.1").is_multicast());
+
+        #[cfg(feature = "tor")]
+        {
+            let onion = sample_onion();
+            assert!(!onion.is_loopback());
+            assert!(!onion.is_unspecified());
+            assert!(!onion.is_multicast());
+            assert!(!onion.is_private());
+        }
+    }
+
+    #[test]
+    fn test_uniform_encoding() {
+        let ipv4 = InetAddr::from(Ipv4Addr::new(127, 0, 0, 1));
+        let ipv6 = InetAddr::from_str("2001:db8::1").unwrap();
+        for addr in [
+            ipv4,
+            ipv6,
+            InetAddr::from(Ipv6Addr::LOCALHOST),
+            #[cfg(feature = "tor")]
+            sample_onion(),
+        ] {
+            assert_eq!(
+                InetAddr::from_uniform_encoding(addr.to_uniform_encoding()),
+                Some(addr)
+            );
+        }
+
+        let data = ipv4.to_uniform_encoding();
+        assert_eq!(data[..16], [0u8; 16]);
+        assert_eq!(data[16..], Ipv4Addr::LOCALHOST.to_ipv6_mapped().octets());
+        assert_eq!(
+            data[..],
+            InetSocketAddrExt::tcp(ipv4, 0).to_compact_bytes()[1..33]
+        );
+
+        // Non-zero leading bytes which are not a valid Tor key
+        let mut data =
+            InetAddr::from(Ipv6Addr::LOCALHOST).to_uniform_encoding();
+        data[15] = 0x01;
+        assert_eq!(InetAddr::from_uniform_encoding(data), None);
+        let mut data = [0u8; 32];
+        data[0] = 0x02;
+        assert_eq!(InetAddr::from_uniform_encoding(data), None);
+
+        // All-zero data is an unspecified IPv6 address, not a Tor key
+        assert_eq!(
+            InetAddr::from_uniform_encoding([0u8; 32]),
+            Some(InetAddr::IPv6(Ipv6Addr::UNSPECIFIED))
+        );
+    }
+
     #[test]
     fn test_transport() {
         assert_eq!(format!("{}", Transport::Tcp), "tcp");
         assert_eq!(format!("{}", Transport::Udp), "udp");
         assert_eq!(format!("{}", Transport::Quic), "quic");
         assert_eq!(format!("{}", Transport::Mtcp), "mtcp");
+        assert_eq!(format!("{}", Transport::Sctp), "sctp");
+        assert_eq!(format!("{}", Transport::Dccp), "dccp");
 
         assert_eq!(Transport::from_str("tcp").unwrap(), Transport::Tcp);
         assert_eq!(Transport::from_str("Tcp").unwrap(), Transport::Tcp);
@@ -676,7 +2412,153 @@ mod test {
         assert_eq!(Transport::from_str("udp").unwrap(), Transport::Udp);
         assert_eq!(Transport::from_str("quic").unwrap(), Transport::Quic);
         assert_eq!(Transport::from_str("mtcp").unwrap(), Transport::Mtcp);
+        assert_eq!(Transport::from_str("sctp").unwrap(), Transport::Sctp);
+        assert_eq!(Transport::from_str("SCTP").unwrap(), Transport::Sctp);
+        assert_eq!(Transport::from_str("Dccp").unwrap(), Transport::Dccp);
+        assert_eq!(Transport::from_str("DCCP").unwrap(), Transport::Dccp);
         assert!(Transport::from_str("xtp").is_err());
+
+        assert_eq!(Transport::from_str("1").unwrap(), Transport::Tcp);
+        assert_eq!(Transport::from_str("4").unwrap(), Transport::Quic);
+        assert_eq!(Transport::from_str("5").unwrap(), Transport::Sctp);
+        assert_eq!(Transport::from_str("6").unwrap(), Transport::Dccp);
+        assert_eq!(
+            Transport::from_str("7"),
+            Err(AddrParseError::UnknownProtocolError(s!("7")))
+        );
+        assert_eq!(
+            Transport::from_str("99"),
+            Err(AddrParseError::UnknownProtocolError(s!("99")))
+        );
+        assert_eq!(
+            Transport::from_str("1000"),
+            Err(AddrParseError::UnknownProtocolError(s!("1000")))
+        );
+        assert!(Transport::from_str("+1").is_err());
+        assert!(Transport::from_str("").is_err());
+        assert_eq!(Transport::try_from(2u8), Ok(Transport::Udp));
+        assert!(Transport::try_from(0u8).is_err());
+        for transport in [
+            Transport::Tcp,
+            Transport::Udp,
+            Transport::Mtcp,
+            Transport::Quic,
+            Transport::Sctp,
+            Transport::Dccp,
+        ] {
+            assert_eq!(
+                Transport::from_str(&(transport as u8).to_string()),
+                Ok(transport)
+            );
+        }
+    }
+
+    #[test]
+    fn test_inet_socket_addr_ext_accessors() {
+        let socket = InetSocketAddr::new(Ipv6Addr::LOCALHOST.into(), 6865);
+        for transport in [Transport::Tcp, Transport::Quic, Transport::Sctp] {
+            let ext = InetSocketAddrExt::with(transport, socket);
+            assert_eq!(ext, InetSocketAddrExt(transport, socket));
+            assert_eq!(ext.transport(), transport);
+            assert_eq!(ext.socket_addr(), socket);
+            assert_eq!(
+                InetSocketAddrExt::with(ext.transport(), ext.socket_addr()),
+                ext
+            );
+        }
+
+        let parsed = InetSocketAddrExt::from_str("udp://[::1]:6865").unwrap();
+        assert_eq!(parsed.transport(), Transport::Udp);
+        assert_eq!(parsed.socket_addr(), socket);
+    }
+
+    #[test]
+    fn test_transport_default_port() {
+        assert_eq!(Transport::Tcp.default_port(), Some(9735));
+        assert_eq!(Transport::Mtcp.default_port(), Some(9735));
+        assert_eq!(Transport::Udp.default_port(), None);
+        assert_eq!(Transport::Quic.default_port(), None);
+        assert_eq!(Transport::Sctp.default_port(), None);
+        assert_eq!(Transport::Dccp.default_port(), None);
+
+        for transport in [
+            Transport::Tcp,
+            Transport::Udp,
+            Transport::Mtcp,
+            Transport::Quic,
+            Transport::Sctp,
+            Transport::Dccp,
+        ] {
+            assert_eq!(transport.standard_scheme(), transport.to_string());
+        }
+
+        let localhost = InetAddr::from(Ipv4Addr::LOCALHOST);
+        assert_eq!(
+            InetSocketAddrExt::with_default_port(Transport::Tcp, localhost),
+            Some(InetSocketAddrExt::tcp(localhost, 9735))
+        );
+        assert_eq!(
+            InetSocketAddrExt::with_default_port(Transport::Udp, localhost),
+            None
+        );
+    }
+
+    #[test]
+    fn test_transport_classification() {
+        for (transport, connection_oriented, reliable) in [
+            (Transport::Tcp, true, true),
+            (Transport::Udp, false, false),
+            (Transport::Mtcp, true, true),
+            (Transport::Quic, true, true),
+            (Transport::Sctp, true, true),
+            (Transport::Dccp, true, false),
+        ] {
+            assert_eq!(
+                transport.is_connection_oriented(),
+                connection_oriented,
+                "{}",
+                transport
+            );
+            assert_eq!(transport.is_reliable(), reliable, "{}", transport);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "strict_encoding")]
+    fn test_inet_socket_addr_ext_strict_encoding() {
+        use strict_encoding::{StrictDecode, StrictEncode};
+
+        let socket = InetSocketAddr::new(Ipv4Addr::LOCALHOST.into(), 6865);
+        for transport in
+            [Transport::Tcp, Transport::Udp, Transport::Mtcp, Transport::Quic]
+        {
+            let ext = InetSocketAddrExt::with(transport, socket);
+            let data = ext.strict_serialize().unwrap();
+            assert_eq!(InetSocketAddrExt::strict_deserialize(data), Ok(ext));
+        }
+        for transport in [Transport::Sctp, Transport::Dccp] {
+            let ext = InetSocketAddrExt::with(transport, socket);
+            assert!(ext.strict_serialize().is_err());
+        }
+    }
+
+    #[test]
+    fn test_transport_negotiate() {
+        use Transport::*;
+
+        assert_eq!(Transport::negotiate(&[Tcp, Udp], &[Udp, Tcp]), Some(Tcp));
+        assert_eq!(
+            Transport::negotiate(&[Udp, Tcp, Quic], &[Mtcp, Quic, Udp]),
+            Some(Quic)
+        );
+        assert_eq!(
+            Transport::negotiate(&[Udp, Mtcp, Tcp], &[Tcp, Mtcp]),
+            Some(Mtcp)
+        );
+        assert_eq!(Transport::negotiate(&[Tcp, Mtcp], &[Udp, Quic]), None);
+        assert_eq!(Transport::negotiate(&[Udp], &[Udp]), Some(Udp));
+        assert_eq!(Transport::negotiate(&[Udp], &[Tcp]), None);
+        assert_eq!(Transport::negotiate(&[], &[Tcp]), None);
     }
 
     #[test]
@@ -717,10 +2599,23 @@ mod test {
         assert_eq!(InetSocketAddr::from_str("127.0.0.1:6865").unwrap(), ip4);
         assert_eq!(InetSocketAddr::from_str("[::1]:6865").unwrap(), ip6);
         assert_eq!(format!("{}", ip4), "127.0.0.1:6865");
-        assert_eq!(format!("{}", ip6), "::1:6865");
+        assert_eq!(format!("{}", ip6), "[::1]:6865");
 
         assert!(!ip4.is_tor());
         assert!(!ip6.is_tor());
+
+        #[cfg(feature = "tor")]
+        let onion = InetSocketAddr::new(sample_onion(), 9735);
+        #[cfg(feature = "tor")]
+        assert_eq!(onion.to_string(), format!("{}.onion:9735", SAMPLE_ONION));
+        for addr in [
+            ip4,
+            ip6,
+            #[cfg(feature = "tor")]
+            onion,
+        ] {
+            assert_eq!(InetSocketAddr::from_str(&addr.to_string()), Ok(addr));
+        }
     }
 
     #[test]
@@ -747,6 +2642,191 @@ mod test {
             ip6
         );
         assert_eq!(format!("{}", ip4), "tcp://127.0.0.1:6865");
-        assert_eq!(format!("{}", ip6), "udp://::1:6865");
+        assert_eq!(format!("{}", ip6), "udp://[::1]:6865");
+
+        for addr in &[
+            "tcp://tcp://127.0.0.1:6865",
+            "tcp://udp://127.0.0.1:6865",
+            "tcp://127.0.0.1:6865://",
+            "://127.0.0.1:6865",
+            "tcp://",
+            "://",
+            "",
+            "127.0.0.1:6865",
+        ] {
+            assert_eq!(
+                InetSocketAddrExt::from_str(addr),
+                Err(AddrParseError::WrongSocketExtFormat(addr.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_compact_bytes() {
+        let ip4 =
+            InetSocketAddrExt::tcp(InetAddr::from([127, 0, 0, 1]), 9735);
+        let data = ip4.to_compact_bytes();
+        assert_eq!(data.len(), InetSocketAddrExt::COMPACT_LEN);
+        assert_eq!(data[0], 1);
+        assert_eq!(data[1..17], [0u8; 16]);
+        assert_eq!(
+            data[17..33],
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 127, 0, 0, 1]
+        );
+        assert_eq!(data[33..], [0x26, 0x07]);
+        assert_eq!(InetSocketAddrExt::from_compact_bytes(&data), Ok(ip4));
+
+        let ip6 =
+            InetSocketAddrExt::udp(InetAddr::from(Ipv6Addr::LOCALHOST), 1);
+        assert_eq!(
+            InetSocketAddrExt::from_compact_bytes(&ip6.to_compact_bytes()),
+            Ok(ip6)
+        );
+
+        assert_eq!(
+            InetSocketAddrExt::from_compact_bytes(&data[..34]),
+            Err(AddrParseError::WrongRawLength(34))
+        );
+        let mut wrong = data.clone();
+        wrong[0] = 0;
+        assert_eq!(
+            InetSocketAddrExt::from_compact_bytes(&wrong),
+            Err(AddrParseError::UnknownProtocolError(s!("0")))
+        );
+
+        #[cfg(feature = "tor")]
+        {
+            let tor = InetSocketAddrExt(
+                Transport::Quic,
+                InetSocketAddr::new(sample_onion(), 9735),
+            );
+            let data = tor.to_compact_bytes();
+            assert_eq!(data.len(), InetSocketAddrExt::COMPACT_LEN);
+            assert_eq!(data[0], 4);
+            assert_eq!(InetSocketAddrExt::from_compact_bytes(&data), Ok(tor));
+        }
+    }
+
+    #[test]
+    fn test_default_port() {
+        assert_eq!(
+            InetSocketAddr::from_str_default_port("127.0.0.1", 1234),
+            Ok(InetSocketAddr::new(InetAddr::from([127, 0, 0, 1]), 1234))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_default_port("127.0.0.1:80", 1234),
+            Ok(InetSocketAddr::new(InetAddr::from([127, 0, 0, 1]), 80))
+        );
+
+        #[cfg(feature = "tor")]
+        {
+            let onion = sample_onion();
+            assert_eq!(
+                InetSocketAddr::from_str_default_port(SAMPLE_ONION, 1234),
+                Ok(InetSocketAddr::new(onion, ONION_DEFAULT_PORT))
+            );
+            assert_eq!(
+                InetSocketAddr::from_str_default_port(
+                    &format!("{}:1235", SAMPLE_ONION),
+                    1234
+                ),
+                Ok(InetSocketAddr::new(onion, 1235))
+            );
+        }
+    }
+
+    #[test]
+    fn test_same_addr_str() {
+        let compressed = "2001:db8::1";
+        let full = "2001:0DB8:0000:0000:0000:0000:0000:0001";
+        assert!(InetAddr::same_addr_str(compressed, full));
+        assert!(!InetAddr::same_addr_str(compressed, "::1"));
+        assert!(!InetAddr::same_addr_str("127.0.0.1", "::ffff:127.0.0.1"));
+        assert!(InetAddr::same_addr_str("localhost", "localhost"));
+        assert!(!InetAddr::same_addr_str("localhost", "127.0.0.1"));
+
+        #[cfg(feature = "tor")]
+        {
+            let upper = SAMPLE_ONION.to_uppercase();
+            assert!(InetAddr::same_addr_str(SAMPLE_ONION, &upper));
+            assert!(InetAddr::same_addr_str(&upper, SAMPLE_ONION));
+            assert!(!InetAddr::same_addr_str(SAMPLE_ONION, compressed));
+        }
+    }
+
+    #[test]
+    fn test_host_port() {
+        let ip4 = InetSocketAddr::from_str("127.0.0.1:9735").unwrap();
+        assert_eq!(ip4.into_host_port(), (s!("127.0.0.1"), 9735));
+
+        let ip6 = InetSocketAddr::from_str("[2001:db8::1]:9735").unwrap();
+        let (host, port) = ip6.into_host_port();
+        assert_eq!((host.as_str(), port), ("[2001:db8::1]", 9735));
+        assert_eq!(
+            InetSocketAddr::from_str(&format!("{}:{}", host, port)),
+            Ok(ip6)
+        );
+
+        #[cfg(feature = "tor")]
+        {
+            let tor = InetSocketAddr::new(sample_onion(), 9735);
+            assert_eq!(
+                tor.into_host_port(),
+                (format!("{}.onion", SAMPLE_ONION), 9735)
+            );
+        }
+    }
+
+    #[test]
+    fn test_reserved_range() {
+        let reserved =
+            ["192.0.2.1", "198.51.100.7", "203.0.113.255", "2001:db8::1"];
+        for addr in &reserved {
+            assert!(InetAddr::from_str(addr).unwrap().is_documentation());
+            assert_eq!(
+                InetAddr::from_str_reject_reserved(addr),
+                Err(AddrParseError::ReservedRange(addr.to_string()))
+            );
+        }
+        assert!(InetAddr::from_str("::ffff:192.0.2.1")
+            .unwrap()
+            .is_documentation());
+        assert!(!InetAddr::from([192, 0, 3, 1]).is_documentation());
+        assert!(!InetAddr::from_str("2001:db9::1").unwrap().is_documentation());
+        assert_eq!(
+            InetAddr::from_str_reject_reserved("1.1.1.1"),
+            Ok(InetAddr::from([1, 1, 1, 1]))
+        );
+
+        assert_eq!(
+            InetSocketAddr::from_str_reject_reserved("192.0.2.1:9735"),
+            Err(AddrParseError::ReservedRange(s!("192.0.2.1:9735")))
+        );
+        assert_eq!(
+            InetSocketAddr::from_str_reject_reserved("[2001:db8::1]:9735"),
+            Err(AddrParseError::ReservedRange(s!("[2001:db8::1]:9735")))
+        );
+        assert!(
+            InetSocketAddr::from_str_reject_reserved("1.1.1.1:9735").is_ok()
+        );
+    }
+
+    #[test]
+    fn test_const_construction() {
+        const LOCALHOST: InetAddr = InetAddr::from_ipv4_octets([127, 0, 0, 1]);
+        const DEFAULT: InetSocketAddr = InetSocketAddr::new(LOCALHOST, 9735);
+        const ROUTES: [InetSocketAddrExt; 2] = [
+            InetSocketAddrExt::tcp(LOCALHOST, 9735),
+            InetSocketAddrExt::udp(
+                InetAddr::from_ipv6_segments([0, 0, 0, 0, 0, 0, 0, 1]),
+                9735,
+            ),
+        ];
+
+        assert_eq!(LOCALHOST, InetAddr::from([127, 0, 0, 1]));
+        assert_eq!(DEFAULT.to_string(), "127.0.0.1:9735");
+        assert_eq!(ROUTES[0], InetSocketAddrExt::tcp(DEFAULT.address, 9735));
+        assert_eq!(ROUTES[1].1.address, InetAddr::from(Ipv6Addr::LOCALHOST));
+        assert_eq!(ROUTES[1].0, Transport::Udp);
     }
 }
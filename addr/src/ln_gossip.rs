@@ -0,0 +1,201 @@
+// Internet2 addresses with support for Tor v3
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//     Martin Habovstiak <martin.habovstiak@gmail.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Decoding of the lists of `net_address` records used in LN gossip
+//! `node_announcement` messages (BOLT-7)
+
+use std::io::{self, Read};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[cfg(feature = "tor")]
+use torut::onion::{OnionAddressV3, TorPublicKeyV3};
+
+use crate::{InetAddr, InetSocketAddr};
+
+/// Errors decoding LN gossip `net_address` list
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum LnGossipError {
+    /// I/O error reading address list data: {0:?}
+    Io(io::ErrorKind),
+
+    /// address record of type {0} exceeds the declared length of the
+    /// address list
+    Truncated(u8),
+
+    /// Tor v3 address record contains invalid public key or checksum
+    InvalidTorKey,
+}
+
+impl From<io::Error> for LnGossipError {
+    #[inline]
+    fn from(err: io::Error) -> Self { LnGossipError::Io(err.kind()) }
+}
+
+/// Reads `net_address` list from the LN gossip `node_announcement` message:
+/// 2-byte big-endian length of the list followed by the address records.
+///
+/// IPv4, IPv6 and Tor v3 (with `tor` feature) records are returned as socket
+/// addresses. Records of the known types which can't be represented by
+/// [`InetAddr`] (deprecated Tor v2, DNS hostnames and Tor v3 without `tor`
+/// feature) are skipped. According to BOLT-7, the first record of an unknown
+/// type stops parsing, and the rest of the list is ignored, since its length
+/// can't be known.
+pub fn ln_gossip_decode_list(
+    mut r: impl Read,
+) -> Result<Vec<InetSocketAddr>, LnGossipError> {
+    let mut len = [0u8; 2];
+    r.read_exact(&mut len)?;
+    let mut data = vec![0u8; u16::from_be_bytes(len) as usize];
+    r.read_exact(&mut data)?;
+
+    let mut data = &data[..];
+    let mut addrs = vec![];
+    while let Some((ty, rest)) = data.split_first() {
+        data = rest;
+        let addr = match ty {
+            1 => {
+                let mut ip = [0u8; 4];
+                ip.copy_from_slice(take(&mut data, 4, *ty)?);
+                Some(InetAddr::IPv4(Ipv4Addr::from(ip)))
+            }
+            2 => {
+                let mut ip = [0u8; 16];
+                ip.copy_from_slice(take(&mut data, 16, *ty)?);
+                Some(InetAddr::IPv6(Ipv6Addr::from(ip)))
+            }
+            3 => {
+                take(&mut data, 10, *ty)?;
+                None
+            }
+            4 => tor_v3(take(&mut data, 35, *ty)?)?,
+            5 => {
+                let host_len = take(&mut data, 1, *ty)?[0];
+                take(&mut data, host_len as usize, *ty)?;
+                None
+            }
+            _ => break,
+        };
+        let port = take(&mut data, 2, *ty)?;
+        if let Some(address) = addr {
+            let port = u16::from_be_bytes([port[0], port[1]]);
+            addrs.push(InetSocketAddr::new(address, port));
+        }
+    }
+    Ok(addrs)
+}
+
+/// Splits `len` bytes from the beginning of the address list data
+fn take<'data>(
+    data: &mut &'data [u8],
+    len: usize,
+    ty: u8,
+) -> Result<&'data [u8], LnGossipError> {
+    if data.len() < len {
+        return Err(LnGossipError::Truncated(ty));
+    }
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Ok(head)
+}
+
+/// Decodes Tor v3 address record consisting of 32-byte public key, 2-byte
+/// checksum and 1-byte version
+#[cfg(feature = "tor")]
+fn tor_v3(record: &[u8]) -> Result<Option<InetAddr>, LnGossipError> {
+    let key = TorPublicKeyV3::from_bytes(&{
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&record[..32]);
+        key
+    })
+    .map_err(|_| LnGossipError::InvalidTorKey)?;
+    if OnionAddressV3::from(&key).get_raw_bytes()[..] != *record {
+        return Err(LnGossipError::InvalidTorKey);
+    }
+    Ok(Some(InetAddr::Tor(key)))
+}
+
+/// Tor v3 addresses are not supported without `tor` feature and are skipped
+#[cfg(not(feature = "tor"))]
+fn tor_v3(_record: &[u8]) -> Result<Option<InetAddr>, LnGossipError> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "tor")]
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_list() {
+        let mut records = vec![1u8, 1, 2, 3, 4, 0x26, 0x07];
+        records.push(2);
+        records.extend(Ipv6Addr::LOCALHOST.octets());
+        records.extend([0x26, 0x08]);
+        // DNS hostname is skipped
+        records.extend([5, 4]);
+        records.extend(b"host");
+        records.extend([0x26, 0x09]);
+        records.push(4);
+        #[cfg(feature = "tor")]
+        let onion = OnionAddressV3::from_str(
+            "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd",
+        )
+        .unwrap();
+        #[cfg(feature = "tor")]
+        records.extend(onion.get_raw_bytes());
+        #[cfg(not(feature = "tor"))]
+        records.extend([0u8; 35]);
+        records.extend([0x26, 0x0A]);
+        // Unknown record type stops parsing
+        records.extend([0x7F, 0xFF, 0xFF]);
+        records.extend([1, 127, 0, 0, 1, 0x26, 0x07]);
+
+        let mut data = (records.len() as u16).to_be_bytes().to_vec();
+        data.extend(&records);
+
+        let expected = vec![
+            InetSocketAddr::new([1, 2, 3, 4].into(), 9735),
+            InetSocketAddr::new(Ipv6Addr::LOCALHOST.into(), 9736),
+            #[cfg(feature = "tor")]
+            InetSocketAddr::new(onion.get_public_key().into(), 9738),
+        ];
+        assert_eq!(ln_gossip_decode_list(&data[..]), Ok(expected));
+
+        assert_eq!(ln_gossip_decode_list(&[0u8, 0][..]), Ok(vec![]));
+        assert_eq!(
+            ln_gossip_decode_list(&[0u8, 3, 1, 1, 2][..]),
+            Err(LnGossipError::Truncated(1))
+        );
+        assert_eq!(
+            ln_gossip_decode_list(&[0u8, 7, 1][..]),
+            Err(LnGossipError::Io(io::ErrorKind::UnexpectedEof))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "tor")]
+    fn test_decode_invalid_tor() {
+        let mut data = vec![0u8, 38, 4];
+        data.extend([0xFFu8; 35]);
+        data.extend([0x26, 0x07]);
+        assert_eq!(
+            ln_gossip_decode_list(&data[..]),
+            Err(LnGossipError::InvalidTorKey)
+        );
+    }
+}
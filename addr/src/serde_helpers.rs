@@ -0,0 +1,110 @@
+// Internet2 addresses with support for Tor v3
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//     Martin Habovstiak <martin.habovstiak@gmail.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Serde helpers for [`InetAddr`] selecting representation depending on the
+//! serialization format: human-readable formats (like JSON or YAML) get the
+//! address string, while binary formats (like bincode or CBOR) get the
+//! 32-byte uniform encoding produced by [`InetAddr::to_uniform_encoding`].
+//!
+//! Use with `#[serde(with = "inet2_addr::serde_helpers")]` field attribute.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::InetAddr;
+
+/// Serializes address as a string for human-readable formats and as 32-byte
+/// uniform encoding for binary formats
+pub fn serialize<S>(addr: &InetAddr, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.collect_str(addr)
+    } else {
+        addr.to_uniform_encoding().serialize(serializer)
+    }
+}
+
+/// Deserializes address serialized with [`serialize`]
+pub fn deserialize<'de, D>(deserializer: D) -> Result<InetAddr, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        let s = String::deserialize(deserializer)?;
+        InetAddr::from_str(&s).map_err(serde::de::Error::custom)
+    } else {
+        let data = <[u8; 32]>::deserialize(deserializer)?;
+        InetAddr::from_uniform_encoding(data).ok_or_else(|| {
+            serde::de::Error::custom("invalid uniform encoding of address")
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Peer {
+        #[serde(with = "crate::serde_helpers")]
+        addr: InetAddr,
+    }
+
+    fn peers() -> Vec<Peer> {
+        vec![
+            Peer {
+                addr: Ipv4Addr::new(127, 0, 0, 1).into(),
+            },
+            Peer {
+                addr: Ipv6Addr::LOCALHOST.into(),
+            },
+            #[cfg(feature = "tor")]
+            Peer {
+                addr: InetAddr::from_str(
+                    "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd",
+                )
+                .unwrap(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_json() {
+        for peer in peers() {
+            let json = serde_json::to_string(&peer).unwrap();
+            assert_eq!(json, format!(r#"{{"addr":"{}"}}"#, peer.addr));
+            assert_eq!(serde_json::from_str::<Peer>(&json).unwrap(), peer);
+        }
+        assert!(serde_json::from_str::<Peer>(r#"{"addr":"host"}"#).is_err());
+    }
+
+    #[test]
+    fn test_bincode() {
+        for peer in peers() {
+            let data = bincode::serialize(&peer).unwrap();
+            assert_eq!(data, peer.addr.to_uniform_encoding());
+            assert_eq!(bincode::deserialize::<Peer>(&data).unwrap(), peer);
+        }
+        let mut data = [0u8; 32];
+        data[0] = 0x02;
+        assert!(bincode::deserialize::<Peer>(&data).is_err());
+    }
+}
@@ -73,6 +73,7 @@ fn inner_enum(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2> {
     let mut from_type = vec![];
     let mut get_type = vec![];
     let mut get_payload = vec![];
+    let mut type_registry = vec![];
     for v in &data.variants {
         let meta = attr_list(&v.attrs, "api", example)?.ok_or_else(|| {
             Error::new(
@@ -108,6 +109,11 @@ fn inner_enum(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2> {
             const #type_const: u16 = #type_id;
         });
 
+        let variant_name = type_name.to_string();
+        type_registry.push(quote_spanned! { v.span() =>
+            (::internet2::TypeId::from_inner(Self::#type_const), #variant_name),
+        });
+
         unmarshaller.push(quote_spanned! { v.span() =>
             map.insert(Self::#type_const, Self::#type_snake as ::internet2::UnmarshallFn<_>);
         });
@@ -208,6 +214,7 @@ fn inner_enum(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2> {
     let from_type = quote! { #( #from_type )* };
     let get_type = quote! { #( #get_type )* };
     let get_payload = quote! { #( #get_payload )* };
+    let type_registry = quote! { #( #type_registry )* };
 
     let encoding_type = match global_encoding {
         EncodingSrategy::Strict => quote! { Strict },
@@ -228,6 +235,13 @@ fn inner_enum(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2> {
             #msg_const
 
             #unmarshall_fn
+
+            /// Returns list of all message types defined by the API together
+            /// with the names of the corresponding enum variants
+            pub fn type_registry() -> Vec<(::internet2::TypeId, &'static str)> {
+                use ::amplify::Wrapper;
+                vec![#type_registry]
+            }
         }
 
         impl ::internet2::TypedEnum for #ident_name {
@@ -4,6 +4,7 @@ extern crate inet2_derive;
 use std::io::Cursor;
 use std::str::FromStr;
 
+use amplify::Wrapper;
 use internet2::{CreateUnmarshaller, TypedEnum, Unmarshall};
 
 #[derive(Clone, PartialEq, Eq, Debug, Api)]
@@ -61,3 +62,49 @@ fn roundtrip() {
     let roundtrip = &*unmarshaller.unmarshall(Cursor::new(payload)).unwrap();
     assert_eq!(&message, roundtrip);
 }
+
+#[test]
+fn type_registry() {
+    let registry = Request::type_registry()
+        .into_iter()
+        .map(|(type_id, name)| (type_id.into_inner(), name))
+        .collect::<Vec<_>>();
+    assert_eq!(registry, vec![
+        (0x0001, "Hello"),
+        (0x0003, "Empty"),
+        (0x0005, "NoArgs"),
+        (0x0103, "AddKeys"),
+    ]);
+    assert!(Request::type_registry()
+        .into_iter()
+        .any(|(type_id, _)| type_id == Request::NoArgs.get_type()));
+}
+
+#[test]
+fn stream() {
+    let unmarshaller = Request::create_unmarshaller();
+
+    let key = secp256k1::PublicKey::from_str(
+        "020388ac0ff72e76002f6bdf1a08638390f0c43125c33688ca9e64cadff86248a6",
+    )
+    .unwrap();
+    let messages = vec![
+        Request::Hello("world".to_owned()),
+        Request::NoArgs,
+        Request::AddKeys(vec![key]),
+    ];
+    let payload = messages
+        .iter()
+        .flat_map(Request::serialize)
+        .collect::<Vec<_>>();
+
+    let decoded = unmarshaller
+        .unmarshall_stream(Cursor::new(payload))
+        .map(|res| (*res.unwrap()).clone())
+        .collect::<Vec<_>>();
+    assert_eq!(decoded, messages);
+
+    let mut stream = unmarshaller.unmarshall_stream(&b"\x01\x00\x05\x00wo"[..]);
+    assert!(stream.next().unwrap().is_err());
+    assert!(stream.next().is_none());
+}
@@ -70,14 +70,34 @@ pub use transport::websocket;
 #[cfg(feature = "zmq")]
 pub use transport::zmqsocket;
 pub use transport::{
-    ftcp, Duplex, FramingProtocol, LocalSocketAddr, RemoteSocketAddr,
-    RoutedFrame,
+    ftcp, Duplex, FramingProtocol, IntoRemoteSocketAddr, LocalSocketAddr,
+    RemoteSocketAddr, RemoteSocketAddrWith, RoutedFrame,
 };
 
 pub const LNP_MSG_MAX_LEN: usize = std::u16::MAX as usize;
 
 pub const LIGHTNING_P2P_DEFAULT_PORT: u16 = 9735;
 
+/// Returns list of the crate features enabled at compile time, which may be
+/// used for logging the effective build configuration
+pub fn active_features() -> Vec<&'static str> {
+    let features = [
+        ("derive", cfg!(feature = "derive")),
+        ("serde", cfg!(feature = "serde")),
+        ("keygen", cfg!(feature = "keygen")),
+        ("tor", cfg!(feature = "tor")),
+        ("url", cfg!(feature = "url")),
+        ("zmq", cfg!(feature = "zmq")),
+        ("websockets", cfg!(feature = "websockets")),
+        ("tracing", cfg!(feature = "tracing")),
+    ];
+    features
+        .iter()
+        .filter(|(_, active)| *active)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
 #[cfg(feature = "zmq")]
 pub use transport::{ZmqSocketAddr, ZmqType, ZMQ_CONTEXT};
 
@@ -173,3 +193,17 @@ pub enum AddrError {
     #[from(NoOnionSupportError)]
     NoOnionSupport,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_active_features() {
+        let features = active_features();
+        assert_eq!(features.contains(&"tor"), cfg!(feature = "tor"));
+        assert_eq!(features.contains(&"zmq"), cfg!(feature = "zmq"));
+        assert_eq!(features.contains(&"keygen"), cfg!(feature = "keygen"));
+        assert_eq!(features.contains(&"serde"), cfg!(feature = "serde"));
+    }
+}
@@ -12,12 +12,13 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 use std::any::Any;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io;
 use std::io::{Read, Write};
 use std::sync::Arc;
 
 use amplify::Wrapper;
+use bitcoin_hashes::hex::{FromHex, ToHex};
 use lightning_encoding::{self, BigSize, LightningDecode};
 use strict_encoding::TlvError;
 
@@ -148,6 +149,94 @@ impl Stream {
 
     #[inline]
     pub fn is_empty(&self) -> bool { self.0.is_empty() }
+
+    /// Serializes stream into the body of a lightning network message, i.e.
+    /// 2-byte big-endian message type followed by the lightning-encoded TLV
+    /// stream. Empty stream produces just the message type bytes.
+    pub fn to_message_bytes(&self, msg_type: u16) -> Vec<u8> {
+        let mut data = msg_type.to_be_bytes().to_vec();
+        lightning_encoding::LightningEncode::lightning_encode(self, &mut data)
+            .expect("Encoding into memory buffer can't fail");
+        data
+    }
+
+    /// Deserializes lightning network message produced by
+    /// [`Stream::to_message_bytes`], returning message type and TLV stream
+    pub fn from_message_bytes(
+        data: impl AsRef<[u8]>,
+    ) -> Result<(u16, Stream), lightning_encoding::Error> {
+        let mut reader = data.as_ref();
+        let msg_type = u16::lightning_decode(&mut reader)?;
+        let stream = Stream::lightning_decode(&mut reader)?;
+        Ok((msg_type, stream))
+    }
+
+    /// Serializes stream into lightning network message like
+    /// [`Stream::to_message_bytes`], prefixing it with the 2-byte big-endian
+    /// message length for the transports which do not preserve message
+    /// boundaries. Fails with [`lightning_encoding::Error::TooLargeData`] if
+    /// the message length does not fit into the prefix.
+    pub fn to_message_bytes_prefixed(
+        &self,
+        msg_type: u16,
+    ) -> Result<Vec<u8>, lightning_encoding::Error> {
+        let msg = self.to_message_bytes(msg_type);
+        if msg.len() > u16::MAX as usize {
+            return Err(lightning_encoding::Error::TooLargeData(msg.len()));
+        }
+        let mut data = (msg.len() as u16).to_be_bytes().to_vec();
+        data.extend(msg);
+        Ok(data)
+    }
+
+    /// Deserializes length-prefixed lightning network message produced by
+    /// [`Stream::to_message_bytes_prefixed`], returning message type and TLV
+    /// stream. The data must contain exactly the number of bytes specified
+    /// by the prefix.
+    pub fn from_message_bytes_prefixed(
+        data: impl AsRef<[u8]>,
+    ) -> Result<(u16, Stream), lightning_encoding::Error> {
+        let mut reader = data.as_ref();
+        let len = u16::lightning_decode(&mut reader)? as usize;
+        match reader.len() {
+            actual if actual < len => {
+                Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+            }
+            actual if actual > len => {
+                Err(lightning_encoding::Error::DataNotEntirelyConsumed)
+            }
+            _ => Stream::from_message_bytes(reader),
+        }
+    }
+
+    /// Returns hex representation of the lightning-encoded stream, in the
+    /// same form as BOLT test vectors are written
+    pub fn to_hex(&self) -> String {
+        lightning_encoding::lightning_serialize(self)
+            .expect("Encoding into memory buffer can't fail")
+            .to_hex()
+    }
+
+    /// Decodes stream from the hex representation of its lightning encoding
+    pub fn from_hex(s: &str) -> Result<Stream, lightning_encoding::Error> {
+        let data = Vec::<u8>::from_hex(s).map_err(|err| {
+            lightning_encoding::Error::DataIntegrityError(err.to_string())
+        })?;
+        Stream::lightning_deserialize(data)
+    }
+
+    /// Decodes stream like [`Stream::lightning_decode`], coalescing reads
+    /// from the reader with a buffer. Each of the record types and values is
+    /// read with small reads, which results in a syscall per read for the
+    /// socket-backed readers, so this method should be used with them instead
+    /// of [`Stream::lightning_decode`]. Reading ahead is not a problem here,
+    /// since TLV stream is not length-prefixed and always consumes all of the
+    /// remaining data.
+    pub fn lightning_decode_buffered<D: Read>(
+        d: D,
+    ) -> Result<Stream, lightning_encoding::Error> {
+        Stream::lightning_decode(io::BufReader::new(d))
+    }
 }
 
 impl strict_encoding::StrictEncode for Stream {
@@ -198,6 +287,8 @@ impl lightning_encoding::LightningDecode for Stream {
         mut d: D,
     ) -> Result<Self, lightning_encoding::Error> {
         let mut set: BTreeMap<Type, RawValue> = bmap! {};
+        let mut seen = BTreeSet::new();
+        let mut prev = None;
         // Reading stream record by record until it is over
         while let Some(ty) = Type::lightning_decode(&mut d)
             .map(Option::Some)
@@ -207,24 +298,138 @@ impl lightning_encoding::LightningDecode for Stream {
             })?
         {
             let val = RawValue::lightning_decode(&mut d)?;
-            if set.contains_key(&ty) {
-                return Err(TlvError::Repeated(ty.into_inner()).into());
-            }
-            if let Some(max) = set.keys().max() {
-                if *max > ty {
-                    return Err(TlvError::Order {
-                        read: ty.into_inner(),
-                        max: max.into_inner(),
-                    }
-                    .into());
-                }
-            }
+            check_tlv_order(prev, ty, &seen)?;
+            seen.insert(ty);
+            prev = Some(ty);
             set.insert(ty, val);
         }
         Ok(Self(set))
     }
 }
 
+/// Checks that TLV record of type `next` may follow the previously decoded
+/// records, i.e. that it was not met before and that the record types are
+/// strictly increasing. `prev` is the type of the last decoded record and
+/// `seen` is the set of all decoded record types.
+///
+/// This check is shared by all TLV decoders, ensuring that they enforce the
+/// same rules.
+pub(crate) fn check_tlv_order(
+    prev: Option<Type>,
+    next: Type,
+    seen: &BTreeSet<Type>,
+) -> Result<(), TlvError> {
+    if prev == Some(next) || seen.contains(&next) {
+        return Err(TlvError::Repeated(next.into_inner()));
+    }
+    match prev {
+        Some(prev) if prev > next => Err(TlvError::Order {
+            read: next.into_inner(),
+            max: prev.into_inner(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Stateful decoder of lightning-encoded TLV streams which receives data in
+/// chunks (for instance, from a non-blocking socket) and yields TLV records as
+/// soon as they are completely received. Partially received records are
+/// retained until the rest of their data arrive with the following chunks.
+///
+/// The decoder enforces the same rules as [`Stream::lightning_decode`]: TLV
+/// record types must be strictly increasing across all of the chunks. After
+/// an error is returned the decoder state is undefined and it must not be
+/// used anymore.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct StreamDecoder {
+    buf: Vec<u8>,
+    last_type: Option<Type>,
+}
+
+impl StreamDecoder {
+    /// Constructs decoder for a new TLV stream
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns number of bytes received but not yet decoded into a record
+    #[inline]
+    pub fn pending_len(&self) -> usize { self.buf.len() }
+
+    /// Feeds next chunk of the stream data to the decoder, returning all TLV
+    /// records which are completed by the chunk
+    pub fn push_bytes(
+        &mut self,
+        data: &[u8],
+    ) -> Result<Vec<(Type, RawValue)>, lightning_encoding::Error> {
+        self.buf.extend_from_slice(data);
+        let mut records = vec![];
+        let mut pos = 0usize;
+        while let Some((ty, value, len)) = self.decode_record(pos)? {
+            self.last_type = Some(ty);
+            records.push((ty, value));
+            pos += len;
+        }
+        self.buf.drain(..pos);
+        Ok(records)
+    }
+
+    /// Completes decoding of the stream, checking that there is no partially
+    /// received record left
+    pub fn finish(self) -> Result<(), lightning_encoding::Error> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let mut cursor = io::Cursor::new(&self.buf);
+        Type::lightning_decode(&mut cursor)?;
+        let len = BigSize::lightning_decode(&mut cursor)
+            .map_err(|_| lightning_encoding::Error::BigSizeEof)?;
+        Err(TlvError::Len {
+            expected: len.into_inner(),
+            actual: (self.buf.len() - cursor.position() as usize) as u64,
+        }
+        .into())
+    }
+
+    /// Decodes record starting at `pos` position of the buffer, returning
+    /// `None` if the record is not yet completely received
+    fn decode_record(
+        &self,
+        pos: usize,
+    ) -> Result<Option<(Type, RawValue, usize)>, lightning_encoding::Error>
+    {
+        let mut cursor = io::Cursor::new(&self.buf[pos..]);
+        let ty = match Type::lightning_decode(&mut cursor) {
+            Err(lightning_encoding::Error::BigSizeNoValue)
+            | Err(lightning_encoding::Error::BigSizeEof) => return Ok(None),
+            res => res?,
+        };
+        // Since types must be strictly increasing, repeated types are
+        // detected by comparing with the last type only, so we do not need to
+        // keep the set of all seen types
+        check_tlv_order(self.last_type, ty, &BTreeSet::new())?;
+        let len = match BigSize::lightning_decode(&mut cursor) {
+            Err(lightning_encoding::Error::BigSizeNoValue)
+            | Err(lightning_encoding::Error::BigSizeEof) => return Ok(None),
+            res => res?.into_inner(),
+        };
+        // Records can't exceed the size of the lightning message, so we do
+        // not buffer more data than that
+        if len > crate::LNP_MSG_MAX_LEN as u64 {
+            return Err(lightning_encoding::Error::DataIntegrityError(
+                format!("TLV record length {} exceeds message size", len),
+            ));
+        }
+        let start = cursor.position() as usize;
+        let end = start + len as usize;
+        let data = cursor.into_inner();
+        if data.len() < end {
+            return Ok(None);
+        }
+        let value = RawValue(Box::from(&data[start..end]));
+        Ok(Some((ty, value, end)))
+    }
+}
+
 // This structure is not used anywhere and should be removed
 #[deprecated(
     since = "0.6.0",
@@ -242,7 +447,8 @@ impl Unmarshall for Unmarshaller {
 
     fn unmarshall(&self, mut reader: impl Read) -> Result<Stream, Self::Error> {
         let mut tlv = Stream::new();
-        let mut prev_type_id = Type(0);
+        let mut seen = BTreeSet::new();
+        let mut prev_type_id = None;
         loop {
             match BigSize::lightning_decode(&mut reader)
                 .map(|big_size| Type(big_size.into_inner()))
@@ -256,21 +462,19 @@ impl Unmarshall for Unmarshaller {
                 // MUST fail to parse the tlv_stream.
                 Err(err) => break Err(err.into()),
 
-                // if decoded types are not monotonically-increasing
-                // MUST fail to parse the tlv_stream.
-                Ok(type_id) if type_id > prev_type_id => {
-                    break Err(Error::TlvStreamWrongOrder)
-                }
-
                 // if decoded `type`s are not strictly-increasing
                 // (including situations when two or more occurrences of the \
                 // same `type` are met)
                 // MUST fail to parse the tlv_stream.
-                Ok(type_id) if tlv.contains_key(&type_id) => {
-                    break Err(Error::TlvStreamDuplicateItem)
-                }
-
                 Ok(type_id) => {
+                    match check_tlv_order(prev_type_id, type_id, &seen) {
+                        Err(TlvError::Repeated(_)) => {
+                            break Err(Error::TlvStreamDuplicateItem)
+                        }
+                        Err(_) => break Err(Error::TlvStreamWrongOrder),
+                        Ok(()) => {}
+                    }
+
                     let rec = if let Some(parser) =
                         self.known_types.get(&type_id)
                     {
@@ -305,7 +509,8 @@ impl Unmarshall for Unmarshaller {
                         rec.downcast_ref::<&[u8]>()
                             .ok_or(Error::InvalidValue)?,
                     );
-                    prev_type_id = type_id;
+                    seen.insert(type_id);
+                    prev_type_id = Some(type_id);
                 }
             }
         }
@@ -353,3 +558,316 @@ impl Unmarshaller {
 impl Default for Unmarshaller {
     fn default() -> Self { Unmarshaller::new() }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use lightning_encoding::LightningEncode;
+
+    use super::*;
+
+    /// Reader counting number of `read` calls issued to it
+    struct CountingReader<R: Read> {
+        inner: R,
+        reads: usize,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            self.inner.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_message_bytes() {
+        let mut stream = Stream::new();
+        stream.insert(Type::from(1usize), [0xA5u8; 2]);
+        stream.insert(Type::from(0xFDusize), [0x01u8]);
+        let data = stream.to_message_bytes(0x0010);
+        assert_eq!(data, vec![
+            0x00, 0x10, 0x01, 0x02, 0xA5, 0xA5, 0xFD, 0x00, 0xFD, 0x01, 0x01
+        ]);
+        assert_eq!(Stream::from_message_bytes(&data), Ok((0x0010, stream)));
+
+        let data = Stream::new().to_message_bytes(0x0011);
+        assert_eq!(data, vec![0x00, 0x11]);
+        assert_eq!(
+            Stream::from_message_bytes(&data),
+            Ok((0x0011, Stream::new()))
+        );
+
+        assert!(Stream::from_message_bytes([0x00]).is_err());
+    }
+
+    #[test]
+    fn test_message_bytes_prefixed() {
+        let mut stream = Stream::new();
+        stream.insert(Type::from(1usize), [0xA5u8; 2]);
+        let data = stream.to_message_bytes_prefixed(0x0010).unwrap();
+        assert_eq!(data, vec![0x00, 0x06, 0x00, 0x10, 0x01, 0x02, 0xA5, 0xA5]);
+        assert_eq!(
+            Stream::from_message_bytes_prefixed(&data),
+            Ok((0x0010, stream))
+        );
+
+        let data = Stream::new().to_message_bytes_prefixed(0x0011).unwrap();
+        assert_eq!(data, vec![0x00, 0x02, 0x00, 0x11]);
+        assert_eq!(
+            Stream::from_message_bytes_prefixed(&data),
+            Ok((0x0011, Stream::new()))
+        );
+
+        assert!(Stream::from_message_bytes_prefixed(&data[..3]).is_err());
+        let excessive = [0x00, 0x02, 0x00, 0x11, 0x00];
+        assert_eq!(
+            Stream::from_message_bytes_prefixed(excessive),
+            Err(lightning_encoding::Error::DataNotEntirelyConsumed)
+        );
+
+        let mut stream = Stream::new();
+        stream.insert(Type::from(1usize), vec![0u8; u16::MAX as usize]);
+        assert!(matches!(
+            stream.to_message_bytes_prefixed(0x0010),
+            Err(lightning_encoding::Error::TooLargeData(_))
+        ));
+    }
+
+    #[test]
+    fn test_hex() {
+        // BOLT-1 `n1` namespace test vectors: `tlv1` with amount_msat
+        // 72057594037927936 and `tlv2` with short channel id 0x0x550
+        let vector = "0108010000000000000002080000000000000226";
+        let stream = Stream::from_hex(vector).unwrap();
+        assert_eq!(stream.len(), 2);
+        assert_eq!(
+            stream.get(&Type::from(1usize)).unwrap().as_inner()[..],
+            [0x01, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            stream.get(&Type::from(2usize)).unwrap().as_inner()[..],
+            [0, 0, 0, 0, 0, 0, 0x02, 0x26]
+        );
+        assert_eq!(stream.to_hex(), vector);
+
+        assert_eq!(Stream::new().to_hex(), "");
+        assert_eq!(Stream::from_hex(""), Ok(Stream::new()));
+        assert!(matches!(
+            Stream::from_hex("010"),
+            Err(lightning_encoding::Error::DataIntegrityError(_))
+        ));
+        assert!(matches!(
+            Stream::from_hex("zz"),
+            Err(lightning_encoding::Error::DataIntegrityError(_))
+        ));
+        // Records out of order
+        assert!(Stream::from_hex("02080000000000000226010101").is_err());
+    }
+
+    #[test]
+    fn test_check_tlv_order() {
+        let seen = bset! {Type(1), Type(3)};
+        assert_eq!(check_tlv_order(None, Type(0), &BTreeSet::new()), Ok(()));
+        assert_eq!(check_tlv_order(Some(Type(3)), Type(4), &seen), Ok(()));
+        assert_eq!(
+            check_tlv_order(Some(Type(3)), Type(2), &seen),
+            Err(TlvError::Order { read: 2, max: 3 })
+        );
+        assert_eq!(
+            check_tlv_order(Some(Type(3)), Type(3), &seen),
+            Err(TlvError::Repeated(3))
+        );
+        assert_eq!(
+            check_tlv_order(Some(Type(3)), Type(3), &BTreeSet::new()),
+            Err(TlvError::Repeated(3))
+        );
+        assert_eq!(
+            check_tlv_order(Some(Type(3)), Type(1), &seen),
+            Err(TlvError::Repeated(1))
+        );
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        // Two records (types 1 and 0xFD) with the second one split inside
+        // its BigSize-encoded type
+        let data = Vec::<u8>::from_hex("0102a5a5fd00fd03010203").unwrap();
+        let mut decoder = StreamDecoder::new();
+        let records = decoder.push_bytes(&data[..5]).unwrap();
+        assert_eq!(records, vec![(
+            Type::from(1usize),
+            RawValue(Box::from(&[0xa5, 0xa5][..]))
+        )]);
+        assert_eq!(decoder.pending_len(), 1);
+        assert_eq!(decoder.push_bytes(&data[5..9]).unwrap(), vec![]);
+        let records = decoder.push_bytes(&data[9..]).unwrap();
+        assert_eq!(records, vec![(
+            Type::from(0xFDusize),
+            RawValue(Box::from(&[1, 2, 3][..]))
+        )]);
+        assert_eq!(decoder.pending_len(), 0);
+        decoder.finish().unwrap();
+
+        // Byte-by-byte feeding
+        let mut decoder = StreamDecoder::new();
+        let records = data
+            .iter()
+            .flat_map(|byte| decoder.push_bytes(&[*byte]).unwrap())
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            Stream::from(records),
+            Stream::lightning_deserialize(&data).unwrap()
+        );
+
+        // Incomplete record
+        let mut decoder = StreamDecoder::new();
+        decoder.push_bytes(&data[..9]).unwrap();
+        assert_eq!(
+            decoder.finish(),
+            Err(TlvError::Len {
+                expected: 3,
+                actual: 1
+            }
+            .into())
+        );
+
+        // Ordering is enforced across the chunks
+        let mut decoder = StreamDecoder::new();
+        decoder.push_bytes(&[0x03, 0x00]).unwrap();
+        assert_eq!(
+            decoder.push_bytes(&[0x01, 0x00]),
+            Err(TlvError::Order { read: 1, max: 3 }.into())
+        );
+        let mut decoder = StreamDecoder::new();
+        decoder.push_bytes(&[0x03, 0x00]).unwrap();
+        assert_eq!(
+            decoder.push_bytes(&[0x03]),
+            Err(TlvError::Repeated(3).into())
+        );
+    }
+
+    #[test]
+    fn test_buffered_decode() {
+        let mut stream = Stream::new();
+        for ty in 0..100usize {
+            stream.insert(Type::from(ty), [ty as u8; 8]);
+        }
+        let data = stream.lightning_serialize().unwrap();
+
+        let mut reader = CountingReader {
+            inner: Cursor::new(data.clone()),
+            reads: 0,
+        };
+        assert_eq!(Stream::lightning_decode(&mut reader).unwrap(), stream);
+        let unbuffered_reads = reader.reads;
+
+        let mut reader = CountingReader {
+            inner: Cursor::new(data),
+            reads: 0,
+        };
+        assert_eq!(
+            Stream::lightning_decode_buffered(&mut reader).unwrap(),
+            stream
+        );
+        assert!(unbuffered_reads >= 300);
+        assert!(reader.reads <= 3);
+    }
+
+    /// Expected result of decoding TLV stream test vector: either list of
+    /// records as `(type, hex-encoded value)` pairs or the decoding error
+    type Expected = Result<Vec<(u64, &'static str)>, lightning_encoding::Error>;
+
+    /// Decodes TLV stream test vectors with [`Stream::lightning_decode`]
+    /// checking that the result matches the expectations
+    fn check_vectors(vectors: Vec<(&str, &str, Expected)>) {
+        for (description, hex, expected) in vectors {
+            let data = Vec::<u8>::from_hex(hex).unwrap();
+            let decoded = Stream::lightning_decode(&data[..]).map(|stream| {
+                stream
+                    .into_iter()
+                    .map(|(ty, value)| (ty.into_inner(), value.to_hex()))
+                    .collect::<Vec<_>>()
+            });
+            let expected = expected.map(|records| {
+                records
+                    .into_iter()
+                    .map(|(ty, value)| (ty, value.to_owned()))
+                    .collect::<Vec<_>>()
+            });
+            assert_eq!(decoded, expected, "test vector: {}", description);
+        }
+    }
+
+    #[test]
+    fn test_bolt1_vectors() {
+        use lightning_encoding::Error::{
+            BigSizeEof, BigSizeNoValue, BigSizeNotCanonical,
+        };
+
+        let eof = lightning_encoding::Error::from(io::ErrorKind::UnexpectedEof);
+        let value_256 = format!("0ffd0201{}", "00".repeat(256));
+
+        check_vectors(vec![
+            // BOLT-1 TLV decoding successes in any namespace
+            ("empty stream", "", Ok(vec![])),
+            ("unknown odd type", "2100", Ok(vec![(0x21, "")])),
+            ("type 0x0201", "fd020100", Ok(vec![(0x201, "")])),
+            ("type 0xfd", "fd00fd00", Ok(vec![(0xfd, "")])),
+            ("type 0xff", "fd00ff00", Ok(vec![(0xff, "")])),
+            ("type 0x02000001", "fe0200000100", Ok(vec![(0x02000001, "")])),
+            (
+                "type 0x0200000000000001",
+                "ff020000000000000100",
+                Ok(vec![(0x0200000000000001, "")]),
+            ),
+            // BOLT-1 TLV decoding successes in `n1` namespace
+            ("tlv1 amount 0", "0100", Ok(vec![(1, "")])),
+            ("tlv1 amount 1", "010101", Ok(vec![(1, "01")])),
+            (
+                "tlv1 and tlv2",
+                "0108010000000000000002080000000000000226",
+                Ok(vec![(1, "0100000000000000"), (2, "0000000000000226")]),
+            ),
+            // BOLT-1 TLV decoding failures in any namespace
+            ("type truncated", "fd", Err(BigSizeEof)),
+            ("type truncated", "fd01", Err(BigSizeEof)),
+            (
+                "not minimally encoded type",
+                "fd000100",
+                Err(BigSizeNotCanonical),
+            ),
+            ("missing length", "fd0101", Err(BigSizeNoValue)),
+            ("length truncated", "0ffd", Err(BigSizeEof)),
+            ("length truncated", "0ffd26", Err(BigSizeEof)),
+            ("missing value", "0ffd2602", Err(eof.clone())),
+            (
+                "not minimally encoded length",
+                "0ffd000100",
+                Err(BigSizeNotCanonical),
+            ),
+            ("value truncated", &value_256, Err(eof)),
+            // BOLT-1 TLV stream decoding failures in any namespace
+            (
+                "valid tlv records but invalid ordering",
+                "0208000000000000022601012a",
+                Err(TlvError::Order { read: 1, max: 2 }.into()),
+            ),
+            (
+                "valid tlv records but duplicate",
+                "0208000000000000023102080000000000000451",
+                Err(TlvError::Repeated(2).into()),
+            ),
+            (
+                "valid tlv records but invalid ordering",
+                "1f000f012a",
+                Err(TlvError::Order { read: 0x0f, max: 0x1f }.into()),
+            ),
+            (
+                "valid tlv records but duplicate",
+                "1f001f012a",
+                Err(TlvError::Repeated(0x1f).into()),
+            ),
+        ]);
+    }
+}
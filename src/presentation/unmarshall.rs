@@ -13,7 +13,8 @@
 
 use std::any::Any;
 use std::collections::BTreeMap;
-use std::io;
+use std::io::{self, Read};
+use std::iter;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
@@ -97,4 +98,39 @@ where
             _phantom: PhantomData,
         }
     }
+
+    /// Decodes sequence of concatenated messages from the `reader`, yielding
+    /// a single message per iteration until the end of the stream is reached.
+    /// Each of the messages must be self-delimited by its type and body
+    /// encoding.
+    ///
+    /// Iteration stops after the first decoding error, which is returned as
+    /// the last item. NB: unknown odd message types are not self-delimited,
+    /// so they consume all remaining stream data.
+    pub fn unmarshall_stream<'a>(
+        &'a self,
+        mut reader: impl io::Read + 'a,
+    ) -> impl Iterator<Item = Result<Arc<T>, Error>> + 'a {
+        let mut failed = false;
+        iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+            // We read the first byte to detect end of the stream and then
+            // chain it back for the message decoding
+            let mut first = [0u8; 1];
+            let res = loop {
+                match reader.read(&mut first) {
+                    Ok(0) => return None,
+                    Ok(_) => {
+                        break self.unmarshall((&first[..]).chain(&mut reader))
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => break Err(err.into()),
+                }
+            };
+            failed = res.is_err();
+            Some(res)
+        })
+    }
 }
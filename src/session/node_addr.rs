@@ -186,10 +186,15 @@ impl fmt::Display for RemoteNodeAddr {
                 "{}{}@{}",
                 self.remote_addr.url_scheme(),
                 self.node_id,
-                self.remote_addr
+                self.remote_addr.to_socket_addr_string()
             )
         } else {
-            write!(f, "{}@{}", self.node_id, self.remote_addr)
+            write!(
+                f,
+                "{}@{}",
+                self.node_id,
+                self.remote_addr.to_socket_addr_string()
+            )
         }
     }
 }
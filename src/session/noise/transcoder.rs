@@ -68,6 +68,14 @@ impl NoiseEncryptor {
         Ok(ciphertext)
     }
 
+    /// Returns number of messages which can be encrypted before the sending
+    /// key gets rotated (BOLT-8 rotates keys after each 1000 encryptions,
+    /// i.e. after each 500 messages)
+    #[inline]
+    pub fn messages_until_rekey(&self) -> usize {
+        NoiseTranscoder::messages_until_rekey(self.sending_nonce)
+    }
+
     fn increment_nonce(&mut self) {
         NoiseTranscoder::increment_nonce(
             &mut self.sending_nonce,
@@ -84,6 +92,11 @@ impl Encrypt for NoiseEncryptor {
             Err(_) => Vec::new(),
         }
     }
+
+    #[inline]
+    fn frame_overhead(&self) -> usize {
+        TAGGED_MESSAGE_LENGTH_HEADER_SIZE + chacha::TAG_SIZE
+    }
 }
 
 #[derive(Debug)]
@@ -189,6 +202,14 @@ impl NoiseDecryptor {
         Ok((Some(message), message_end_index))
     }
 
+    /// Returns number of messages which can be decrypted before the
+    /// receiving key gets rotated (BOLT-8 rotates keys after each 1000
+    /// decryptions, i.e. after each 500 messages)
+    #[inline]
+    pub fn messages_until_rekey(&self) -> usize {
+        NoiseTranscoder::messages_until_rekey(self.receiving_nonce)
+    }
+
     fn increment_nonce(&mut self) {
         NoiseTranscoder::increment_nonce(
             &mut self.receiving_nonce,
@@ -308,6 +329,13 @@ impl NoiseTranscoder {
         self.decryptor.decrypt_single_message(new_data)
     }
 
+    /// Each message consumes two nonces: one for the length header and one
+    /// for the message body
+    #[inline]
+    fn messages_until_rekey(nonce: u32) -> usize {
+        ((KEY_ROTATION_INDEX - nonce + 1) / 2) as usize
+    }
+
     fn increment_nonce(
         nonce: &mut u32,
         chaining_key: &mut SymmetricKey,
@@ -334,6 +362,11 @@ impl Encrypt for NoiseTranscoder {
             Err(_) => Vec::new(),
         }
     }
+
+    #[inline]
+    fn frame_overhead(&self) -> usize {
+        TAGGED_MESSAGE_LENGTH_HEADER_SIZE + chacha::TAG_SIZE
+    }
 }
 
 impl Decrypt for NoiseTranscoder {
@@ -457,6 +490,31 @@ mod tests {
         assert_eq!(encrypted_messages[1001], Vec::<u8>::from_hex("2ecd8c8a5629d0d02ab457a0fdd0f7b90a192cd46be5ecb6ca570bfc5e268338b1a16cf4ef2d36").unwrap());
     }
 
+    #[test]
+    fn test_messages_until_rekey() {
+        let (mut connected_peer, mut remote_peer) = setup_peers();
+        let message = Vec::<u8>::from_hex("68656c6c6f").unwrap();
+
+        assert_eq!(connected_peer.encryptor.messages_until_rekey(), 500);
+        assert_eq!(remote_peer.decryptor.messages_until_rekey(), 500);
+        for no in 0..1002 {
+            let expected = 500 - no % 500;
+            assert_eq!(
+                connected_peer.encryptor.messages_until_rekey(),
+                expected
+            );
+            assert_eq!(remote_peer.decryptor.messages_until_rekey(), expected);
+            let encrypted = connected_peer.encrypt_buf(&message).unwrap();
+            let decrypted = remote_peer
+                .decrypt_single_message(Some(&encrypted))
+                .unwrap()
+                .unwrap();
+            assert_eq!(decrypted, message);
+        }
+        assert_eq!(connected_peer.encryptor.messages_until_rekey(), 498);
+        assert_eq!(remote_peer.decryptor.messages_until_rekey(), 498);
+    }
+
     #[test]
     fn test_decryption_buffering() {
         let (mut connected_peer, mut remote_peer) = setup_peers();
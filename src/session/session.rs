@@ -18,10 +18,11 @@ use inet2_addr::InetSocketAddr;
 
 use super::{Decrypt, Encrypt, Transcode};
 #[cfg(feature = "keygen")]
-use crate::session::noise::HandshakeState;
+use crate::session::noise::{HandshakeError, HandshakeState};
 use crate::session::{noise, PlainTranscoder};
 use crate::transport::{
     brontide, ftcp, Duplex, Error, RecvFrame, RoutedFrame, SendFrame,
+    MAX_FRAME_PAYLOAD_SIZE,
 };
 #[cfg(feature = "zmq")]
 use crate::zmqsocket;
@@ -51,10 +52,7 @@ pub trait Split {
 pub trait Input {
     fn recv_raw_message(&mut self) -> Result<Vec<u8>, Error>;
     fn recv_routed_message(&mut self) -> Result<RoutedFrame, Error> {
-        // We panic here because this is a program architecture design
-        // error and developer must be notified about it; the program using
-        // this pattern can't work
-        panic!("Multipeer sockets are not possible with the chosen transport")
+        Err(Error::MultipeerUnsupported)
     }
 }
 
@@ -67,10 +65,7 @@ pub trait Output {
         _dest: &[u8],
         _raw: &[u8],
     ) -> Result<usize, Error> {
-        // We panic here because this is a program architecture design
-        // error and developer must be notified about it; the program using
-        // this pattern can't work
-        panic!("Multipeer sockets are not possible with the chosen transport")
+        Err(Error::MultipeerUnsupported)
     }
 }
 
@@ -85,6 +80,9 @@ where
 {
     pub(self) transcoder: T,
     pub(self) connection: C,
+    /// Static public key of the remote peer, known only for the encrypted
+    /// sessions once the handshake is completed
+    pub(self) remote_static_key: Option<secp256k1::PublicKey>,
 }
 
 pub struct RawInput<D, R>
@@ -222,21 +220,18 @@ impl Session for Raw<NoiseTranscoder, brontide::Connection> {
     fn send_raw_message(&mut self, raw: &[u8]) -> Result<usize, Error> {
         InternalSession::send_raw_message(self, raw)
     }
+    // To route brontide messages use presentation-level onion routing
     fn recv_routed_message(&mut self) -> Result<RoutedFrame, Error> {
-        unimplemented!(
-            "to route brontide messages use presentation-level onion routing"
-        )
+        Err(Error::MultipeerUnsupported)
     }
     fn send_routed_message(
         &mut self,
-        source: &[u8],
-        route: &[u8],
-        dest: &[u8],
-        raw: &[u8],
+        _source: &[u8],
+        _route: &[u8],
+        _dest: &[u8],
+        _raw: &[u8],
     ) -> Result<usize, Error> {
-        unimplemented!(
-            "to route brontide messages use presentation-level onion routing"
-        )
+        Err(Error::MultipeerUnsupported)
     }
     #[inline]
     fn into_any(self: Box<Self>) -> Box<dyn Any> { self }
@@ -292,6 +287,26 @@ where
     }
 }
 
+impl<T, C> Raw<T, C>
+where
+    T: Transcode,
+    T::Left: Decrypt,
+    T::Right: Encrypt,
+    C: Duplex + Bipolar,
+    C::Left: RecvFrame,
+    C::Right: SendFrame,
+{
+    /// Returns maximal size of the message payload which can be sent within
+    /// a single frame, taking into account frame size limit of the
+    /// transport and length header and MACs added by the session encryption
+    pub fn max_payload(&self) -> usize {
+        self.connection
+            .max_frame_size()
+            .saturating_sub(self.transcoder.frame_overhead())
+            .min(MAX_FRAME_PAYLOAD_SIZE)
+    }
+}
+
 impl Raw<PlainTranscoder, ftcp::Connection> {
     pub fn with_ftcp_unencrypted(
         stream: std::net::TcpStream,
@@ -300,6 +315,7 @@ impl Raw<PlainTranscoder, ftcp::Connection> {
         Ok(Self {
             transcoder: PlainTranscoder,
             connection: ftcp::Connection::with(stream, socket_addr),
+            remote_static_key: None,
         })
     }
 
@@ -309,6 +325,7 @@ impl Raw<PlainTranscoder, ftcp::Connection> {
         Ok(Self {
             transcoder: PlainTranscoder,
             connection: ftcp::Connection::connect(socket_addr)?,
+            remote_static_key: None,
         })
     }
 
@@ -318,10 +335,36 @@ impl Raw<PlainTranscoder, ftcp::Connection> {
         Ok(Self {
             transcoder: PlainTranscoder,
             connection: ftcp::Connection::accept(socket_addr)?,
+            remote_static_key: None,
         })
     }
 }
 
+impl Raw<NoiseTranscoder, brontide::Connection> {
+    /// Returns static public key of the remote peer (in compressed form),
+    /// which was learned during Noise_XK handshake. Returns `None` if the
+    /// handshake was not completed.
+    #[inline]
+    pub fn remote_static_key(&self) -> Option<[u8; 33]> {
+        self.remote_static_key.map(|pk| pk.serialize())
+    }
+}
+
+/// Converts handshake state machine error into [`Error::HandshakeFailed`],
+/// distinguishing handshake rejection from the network errors
+#[cfg(feature = "keygen")]
+fn handshake_failed(err: HandshakeError) -> Error {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(error = %err, "Noise_XK handshake failed");
+    Error::HandshakeFailed(match err {
+        HandshakeError::General(reason) => reason,
+        HandshakeError::ChaCha20(_) => s!(
+            "unable to authenticate handshake act, probably due to the remote \
+             static key mismatch"
+        ),
+    })
+}
+
 #[cfg(feature = "keygen")]
 impl Raw<NoiseTranscoder, brontide::Connection> {
     pub fn connect_ftcp_encrypted(
@@ -342,24 +385,27 @@ impl Raw<NoiseTranscoder, brontide::Connection> {
         let mut connection = brontide::Connection::connect(remote_addr)?;
 
         let mut data = vec![];
-        let transcoder = loop {
-            let (act, h) = handshake.next(&data)?;
+        let (transcoder, remote_static_key) = loop {
+            let (act, h) = handshake.next(&data).map_err(handshake_failed)?;
             handshake = h;
             if let Some(ref act) = act {
                 connection.as_sender().send_raw(&*act)?;
                 if let HandshakeState::Complete(Some((transcoder, pk))) =
                     handshake
                 {
-                    break transcoder;
+                    break (transcoder, pk);
                 }
                 data =
                     connection.as_receiver().recv_raw(handshake.data_len())?;
             }
         };
 
+        #[cfg(feature = "tracing")]
+        tracing::debug!(remote = %remote_addr, "Noise_XK handshake completed");
         Ok(Self {
             transcoder,
             connection,
+            remote_static_key: Some(remote_static_key),
         })
     }
 
@@ -378,12 +424,12 @@ impl Raw<NoiseTranscoder, brontide::Connection> {
 
         let mut data =
             connection.as_receiver().recv_raw(handshake.data_len())?;
-        let transcoder = loop {
-            let (act, h) = handshake.next(&data)?;
+        let (transcoder, remote_static_key) = loop {
+            let (act, h) = handshake.next(&data).map_err(handshake_failed)?;
             handshake = h;
             if let HandshakeState::Complete(Some((transcoder, pk))) = handshake
             {
-                break transcoder;
+                break (transcoder, pk);
             }
             if let Some(act) = act {
                 connection.as_sender().send_raw(&*act)?;
@@ -392,9 +438,12 @@ impl Raw<NoiseTranscoder, brontide::Connection> {
             }
         };
 
+        #[cfg(feature = "tracing")]
+        tracing::debug!(remote = %remote_addr, "Noise_XK handshake completed");
         Ok(Self {
             transcoder,
             connection,
+            remote_static_key: Some(remote_static_key),
         })
     }
 }
@@ -412,6 +461,7 @@ impl Raw<PlainTranscoder, zmqsocket::Connection> {
             connection: zmqsocket::Connection::with(
                 zmq_type, remote, local, identity,
             )?,
+            remote_static_key: None,
         })
     }
 
@@ -424,6 +474,7 @@ impl Raw<PlainTranscoder, zmqsocket::Connection> {
             connection: zmqsocket::Connection::from_zmq_socket(
                 zmq_type, socket,
             ),
+            remote_static_key: None,
         }
     }
 }
@@ -521,7 +572,64 @@ where
 
 #[cfg(test)]
 mod test {
+    use std::net::{TcpListener, TcpStream};
+
     use super::*;
+    use crate::transport::MAX_FRAME_SIZE;
+
+    #[test]
+    fn test_max_payload() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stream = TcpStream::connect(addr).unwrap();
+
+        let plain =
+            Raw::with_ftcp_unencrypted(stream.try_clone().unwrap(), addr.into())
+                .unwrap();
+        assert_eq!(plain.max_payload(), MAX_FRAME_PAYLOAD_SIZE);
+
+        // Noise frames carry 2-byte length header and two 16-byte MACs, which
+        // fit exactly into the frame prefix and suffix
+        let encrypted = Raw {
+            transcoder: NoiseTranscoder::new([1u8; 32], [2u8; 32], [3u8; 32]),
+            connection: ftcp::Connection::with(stream, addr.into()),
+            remote_static_key: None,
+        };
+        assert_eq!(encrypted.transcoder.frame_overhead(), 2 + 16 + 16);
+        assert_eq!(encrypted.max_payload(), MAX_FRAME_PAYLOAD_SIZE);
+        assert_eq!(
+            encrypted.max_payload(),
+            MAX_FRAME_SIZE - plain.transcoder.frame_overhead()
+        );
+    }
+
+    #[test]
+    fn test_routed_unsupported() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let stream = TcpStream::connect(addr).unwrap();
+
+        let mut session =
+            Raw::with_ftcp_unencrypted(stream, addr.into()).unwrap();
+        assert_eq!(
+            Session::recv_routed_message(&mut session),
+            Err(Error::MultipeerUnsupported)
+        );
+        assert_eq!(
+            Session::send_routed_message(&mut session, b"a", b"b", b"c", b"d"),
+            Err(Error::MultipeerUnsupported)
+        );
+
+        let (mut input, mut output) = session.split();
+        assert_eq!(
+            input.recv_routed_message(),
+            Err(Error::MultipeerUnsupported)
+        );
+        assert_eq!(
+            output.send_routed_message(b"a", b"b", b"c", b"d"),
+            Err(Error::MultipeerUnsupported)
+        );
+    }
 
     #[test]
     #[cfg(feature = "zmq")]
@@ -21,6 +21,11 @@ use crate::transport::{
 
 pub trait Encrypt {
     fn encrypt(&mut self, buffer: impl Borrow<[u8]>) -> Vec<u8>;
+
+    /// Returns number of bytes which are added to each of the encrypted
+    /// messages on top of the message payload (length header and MACs)
+    #[inline]
+    fn frame_overhead(&self) -> usize { FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE }
 }
 
 pub trait Decrypt {
@@ -14,14 +14,52 @@
 //! Framed TCP protocol: reads & writes frames (corresponding to LNP messages)
 //! from TCP stream
 
-use std::net::TcpStream;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::Read;
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::ops::RangeInclusive;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use amplify::Bipolar;
-use inet2_addr::InetSocketAddr;
+use inet2_addr::{InetAddr, InetSocketAddr};
+use lightning_encoding::{LightningDecode, LightningEncode};
 
-use super::{Duplex, Error, RecvFrame, SendFrame};
+use super::{
+    validate_frame, Duplex, Error, RecvFrame, SendFrame, FRAME_PREFIX_SIZE,
+    FRAME_SUFFIX_SIZE, MAX_FRAME_PAYLOAD_SIZE,
+};
+use crate::presentation::tlv;
+use crate::session::{Decrypt, Encrypt, PlainTranscoder};
 use crate::transport::generic::{self, TcpInetStream};
 
+/// TLV record type in the version negotiation frame holding minimal protocol
+/// version supported by the peer
+const TLV_VERSION_MIN: usize = 0;
+
+/// TLV record type in the version negotiation frame holding maximal protocol
+/// version supported by the peer
+const TLV_VERSION_MAX: usize = 2;
+
+/// Value filling reserved bytes of the frame prefix in the close frame sent by
+/// [`Connection::send_close`]. Plaintext frames always have these bytes
+/// zeroed, while encrypted frames keep MAC in them, so close frame can't be
+/// confused with a data frame.
+const CLOSE_FRAME_TAG: u8 = 0xFF;
+
+/// Magic bytes identifying LNP protocol which may be used with
+/// [`Connection::exchange_magic`]
+pub const LNP_MAGIC: [u8; 4] = *b"LNP\0";
+
+/// Default address of SOCKS5 proxy run by Tor daemon, which is used by
+/// [`Connection::connect_tor`]
+pub const TOR_SOCKS5_PROXY: InetSocketAddr =
+    InetSocketAddr::new(InetAddr::IPv4(Ipv4Addr::LOCALHOST), 9050);
+
+/// Time period for which [`Listener`] accept rate limit is applied
+const ACCEPT_RATE_PERIOD: Duration = Duration::from_secs(1);
+
 /// Type alias for FTCP connection which is [`generic::Connection`] with FTCP
 /// [`Stream`].
 pub type Connection = generic::Connection<Stream>;
@@ -30,15 +68,449 @@ pub type Connection = generic::Connection<Stream>;
 #[derive(Debug, From)]
 pub struct Stream(TcpStream);
 
+/// Emits tracing event reporting the result of connecting to the remote
+/// `inet_addr` (optionally through the SOCKS5 `proxy`) or, if `accepted` is
+/// set, of accepting connection on the local `inet_addr`
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn trace_established(
+    stream: Result<TcpStream, Error>,
+    inet_addr: InetSocketAddr,
+    proxy: Option<InetSocketAddr>,
+    accepted: bool,
+) -> Result<TcpStream, Error> {
+    #[cfg(feature = "tracing")]
+    {
+        let proxy = proxy.map(tracing::field::display);
+        match (&stream, accepted) {
+            (Ok(_), false) => tracing::debug!(
+                remote = %inet_addr, proxy, "FTCP connected"
+            ),
+            (Err(err), false) => tracing::warn!(
+                remote = %inet_addr, proxy, error = %err,
+                "FTCP connection failed"
+            ),
+            (Ok(_), true) => {
+                tracing::debug!(local = %inet_addr, "FTCP accepted")
+            }
+            (Err(err), true) => tracing::warn!(
+                local = %inet_addr, error = %err, "FTCP accept failed"
+            ),
+        }
+    }
+    stream
+}
+
 impl Connection {
+    /// Connects to the remote peer. Onion addresses are connected through
+    /// Tor daemon SOCKS5 proxy at the default [`TOR_SOCKS5_PROXY`] address.
+    #[inline]
     pub fn connect(inet_addr: InetSocketAddr) -> Result<Self, Error> {
-        let stream = TcpStream::connect_inet_socket(inet_addr)?;
-        Ok(Connection::with(stream, inet_addr))
+        if inet_addr.is_tor() {
+            return Self::connect_tor(inet_addr);
+        }
+        Self::connect_from(inet_addr, None)
+    }
+
+    /// Connects to the remote peer (which may have either IP or onion
+    /// address) through Tor daemon SOCKS5 proxy at the default
+    /// [`TOR_SOCKS5_PROXY`] address
+    #[inline]
+    pub fn connect_tor(inet_addr: InetSocketAddr) -> Result<Self, Error> {
+        Self::connect_socks5(inet_addr, TOR_SOCKS5_PROXY)
+    }
+
+    /// Connects to the remote peer through SOCKS5 proxy listening at `proxy`
+    /// address. Onion addresses are passed to the proxy as host names, so
+    /// they are resolved by the proxy (which must be a Tor daemon in this
+    /// case).
+    ///
+    /// # Errors
+    /// * [`Error::ConnectionRefused`] if the proxy is not running
+    /// * [`Error::Socks5`] if the proxy has failed to connect to the remote
+    ///   peer
+    pub fn connect_socks5(
+        inet_addr: InetSocketAddr,
+        proxy: InetSocketAddr,
+    ) -> Result<Self, Error> {
+        let stream = TcpStream::connect_inet_socket(proxy).and_then(
+            |mut stream| {
+                generic::socks5_connect(&mut stream, inet_addr)?;
+                Ok(stream)
+            },
+        );
+        let stream = trace_established(stream, inet_addr, Some(proxy), false);
+        Ok(Connection::with(stream?, inet_addr))
+    }
+
+    /// Parses socket address string, which must contain port number, and
+    /// connects to the remote peer. Address parse errors are reported as
+    /// [`Error::AddrParse`].
+    pub fn connect_str(addr: &str) -> Result<Self, Error> {
+        Self::connect(InetSocketAddr::from_str_port_required(addr)?)
+    }
+
+    /// Connects to the remote peer binding local side of the connection to
+    /// `bind_source` address, if provided. This allows to select the network
+    /// interface for outgoing connections on multi-homed hosts.
+    pub fn connect_from(
+        inet_addr: InetSocketAddr,
+        bind_source: Option<InetAddr>,
+    ) -> Result<Self, Error> {
+        let stream =
+            TcpStream::connect_inet_socket_from(inet_addr, bind_source);
+        let stream = trace_established(stream, inet_addr, None, false);
+        Ok(Connection::with(stream?, inet_addr))
+    }
+
+    /// Connects to the remote peer failing with [`Error::TimedOut`] if the
+    /// connection was not established within the `timeout`
+    pub fn connect_timeout(
+        inet_addr: InetSocketAddr,
+        timeout: Duration,
+    ) -> Result<Self, Error> {
+        let stream = TcpStream::connect_inet_socket_timeout(inet_addr, timeout);
+        let stream = trace_established(stream, inet_addr, None, false);
+        Ok(Connection::with(stream?, inet_addr))
     }
 
     pub fn accept(inet_addr: InetSocketAddr) -> Result<Self, Error> {
-        let stream = TcpStream::accept_inet_socket(inet_addr)?;
-        Ok(Connection::with(stream, inet_addr))
+        let stream = TcpStream::accept_inet_socket(inet_addr);
+        let stream = trace_established(stream, inet_addr, None, true);
+        Ok(Connection::with(stream?, inet_addr))
+    }
+
+    /// Exchanges 4-byte protocol `magic` and 1-byte protocol `version` with
+    /// the remote peer as the very first bytes sent over the connection,
+    /// before any frames. This allows to detect peers speaking a different
+    /// protocol which have connected to (or accepted connection on) a wrong
+    /// port.
+    ///
+    /// The exchange is optional and must be performed by both peers, so it
+    /// should be used only when it is known that the remote peer supports it.
+    ///
+    /// # Returns
+    /// Protocol version byte sent by the remote peer
+    ///
+    /// # Errors
+    /// * [`Error::HandshakeFailed`] if the remote peer magic differs
+    /// * I/O errors from sending and receiving data
+    pub fn exchange_magic(
+        &mut self,
+        magic: [u8; 4],
+        version: u8,
+    ) -> Result<u8, Error> {
+        let mut local = [0u8; 5];
+        local[..4].copy_from_slice(&magic);
+        local[4] = version;
+        self.as_sender().send_raw(&local)?;
+
+        let remote = self.as_receiver().recv_raw(local.len())?;
+        if remote[..4] != magic {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                label = self.label(),
+                magic = ?&remote[..4],
+                "FTCP peer sent wrong protocol magic"
+            );
+            return Err(Error::HandshakeFailed(s!("wrong protocol magic")));
+        }
+        Ok(remote[4])
+    }
+
+    /// Negotiates protocol version with the remote peer. Must be called by
+    /// both peers right after the connection is established and before any
+    /// other frame is sent.
+    ///
+    /// The first frame sent by each of the peers is a TLV stream with the
+    /// range of the supported protocol versions. The highest version which
+    /// is supported by both peers is returned and remains accessible via
+    /// [`Connection::negotiated_version`].
+    ///
+    /// # Errors
+    /// * [`Error::HandshakeFailed`] if the peer version ranges do not
+    ///   overlap or the peer version frame is malformed
+    /// * any of I/O and frame errors from sending and receiving frames
+    pub fn negotiate_version(
+        &mut self,
+        versions: RangeInclusive<u16>,
+    ) -> Result<u16, Error> {
+        let mut stream = tlv::Stream::new();
+        stream.insert(TLV_VERSION_MIN.into(), versions.start().to_be_bytes());
+        stream.insert(TLV_VERSION_MAX.into(), versions.end().to_be_bytes());
+        let payload = stream
+            .lightning_serialize()
+            .expect("Encoding into memory buffer can't fail");
+        self.as_sender()
+            .send_frame(&PlainTranscoder.encrypt(payload))?;
+
+        let frame = self.as_receiver().recv_frame()?;
+        let payload = PlainTranscoder.decrypt(frame)?;
+        let remote = tlv::Stream::lightning_deserialize(&payload)
+            .ok()
+            .and_then(|stream| {
+                let min = stream.get(&TLV_VERSION_MIN.into())?;
+                let max = stream.get(&TLV_VERSION_MAX.into())?;
+                let min = u16::lightning_deserialize(min).ok()?;
+                let max = u16::lightning_deserialize(max).ok()?;
+                Some(min..=max)
+            })
+            .ok_or_else(|| {
+                Error::HandshakeFailed(s!("malformed protocol version frame"))
+            })?;
+
+        let min = *versions.start().max(remote.start());
+        let max = *versions.end().min(remote.end());
+        if min > max {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                label = self.label(),
+                "FTCP protocol version negotiation failed"
+            );
+            return Err(Error::HandshakeFailed(format!(
+                "no common protocol version: local versions are {:?}, \
+                 remote versions are {:?}",
+                versions, remote
+            )));
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            label = self.label(),
+            version = max,
+            "FTCP protocol version negotiated"
+        );
+        self.negotiated_version = Some(max);
+        Ok(max)
+    }
+
+    /// Gracefully closes the connection, notifying the remote peer about the
+    /// `reason`. Sends the final close frame and shuts down the write side of
+    /// the connection; the frames already sent by the remote peer can still
+    /// be received.
+    ///
+    /// The remote peer receives [`Error::ClosedByPeer`] with the `reason`
+    /// from its [`RecvFrame::recv_frame`] call.
+    ///
+    /// # Errors
+    /// * [`Error::OversizedFrame`] if the `reason` does not fit into a frame
+    /// * I/O errors from sending the frame and shutting down the socket
+    #[inline]
+    pub fn send_close(&mut self, reason: &[u8]) -> Result<(), Error> {
+        self.stream.send_close(reason)
+    }
+
+    /// Reads the next frame and discards it without allocating memory for
+    /// its payload, returning the number of the skipped payload bytes.
+    ///
+    /// Useful for filtering out unneeded messages after their type is
+    /// peeked from the socket.
+    ///
+    /// # Errors
+    /// * [`Error::ClosedByPeer`] if the skipped frame is a close frame
+    /// * I/O errors from reading the socket
+    #[inline]
+    pub fn skip_frame(&mut self) -> Result<usize, Error> {
+        self.stream.skip_frame()
+    }
+
+    /// Returns protocol version agreed with the remote peer during
+    /// [`Connection::negotiate_version`] call, or `None` if the negotiation
+    /// was not performed
+    #[inline]
+    pub fn negotiated_version(&self) -> Option<u16> { self.negotiated_version }
+
+    /// Sets timeout for the read operations on the connection; `None` means
+    /// that the reads block indefinitely. Reads exceeding the timeout fail
+    /// with [`Error::TimedOut`].
+    ///
+    /// NB: connections are created with 30-second read timeout, which is
+    /// used for handling ping-pong cycles.
+    ///
+    /// # Errors
+    /// [`Error::SocketIo`] with [`std::io::ErrorKind::InvalidInput`] if the
+    /// zero duration is provided
+    #[inline]
+    pub fn set_read_timeout(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        Ok(self.stream.0.set_read_timeout(timeout)?)
+    }
+
+    /// Sets timeout for the write operations on the connection; `None` (the
+    /// default) means that the writes block indefinitely. Writes exceeding
+    /// the timeout fail with [`Error::TimedOut`].
+    ///
+    /// # Errors
+    /// [`Error::SocketIo`] with [`std::io::ErrorKind::InvalidInput`] if the
+    /// zero duration is provided
+    #[inline]
+    pub fn set_write_timeout(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        Ok(self.stream.0.set_write_timeout(timeout)?)
+    }
+
+    /// Returns timeout of the read operations on the connection
+    #[inline]
+    pub fn read_timeout(&self) -> Result<Option<Duration>, Error> {
+        Ok(self.stream.0.read_timeout()?)
+    }
+
+    /// Returns timeout of the write operations on the connection
+    #[inline]
+    pub fn write_timeout(&self) -> Result<Option<Duration>, Error> {
+        Ok(self.stream.0.write_timeout()?)
+    }
+}
+
+/// FTCP listener accepting incoming connections on a bound TCP socket.
+///
+/// Unlike [`Connection::accept`], which binds a new socket for each of the
+/// accepted connections, the listener keeps the socket bound and may limit
+/// the rate at which new connections are accepted, mitigating connection
+/// flood attacks.
+#[derive(Debug)]
+pub struct Listener {
+    listener: TcpListener,
+    max_accept_rate: Option<u32>,
+    reject_excess: bool,
+    accepted: VecDeque<Instant>,
+}
+
+impl Listener {
+    /// Binds listener to the provided socket address. Port 0 requests the
+    /// system to assign an ephemeral port, which can be retrieved with
+    /// [`Listener::local_addr`].
+    pub fn bind(inet_addr: InetSocketAddr) -> Result<Self, Error> {
+        let socket_addr = SocketAddr::try_from(inet_addr)
+            .map_err(|_| Error::TorNotSupportedYet)?;
+        Ok(Listener {
+            listener: TcpListener::bind(socket_addr)?,
+            max_accept_rate: None,
+            reject_excess: false,
+            accepted: VecDeque::new(),
+        })
+    }
+
+    /// Returns local address the listener is bound to
+    #[inline]
+    pub fn local_addr(&self) -> Result<InetSocketAddr, Error> {
+        Ok(self.listener.local_addr()?.into())
+    }
+
+    /// Returns maximum number of connections accepted per second, if limited
+    #[inline]
+    pub fn max_accept_rate(&self) -> Option<u32> { self.max_accept_rate }
+
+    /// Limits number of connections accepted per second. When the limit is
+    /// reached, [`Listener::accept`] waits until the next connection can be
+    /// accepted within the limit (or rejects the excessive connections, see
+    /// [`Listener::set_reject_excess`]). `None` removes the limit; zero
+    /// limit blocks acceptance of any connections.
+    #[inline]
+    pub fn set_max_accept_rate(&mut self, max_accept_rate: Option<u32>) {
+        self.max_accept_rate = max_accept_rate;
+    }
+
+    /// Sets whether connections exceeding the accept rate limit must be
+    /// closed immediately instead of being deferred until the rate allows
+    /// to accept them
+    #[inline]
+    pub fn set_reject_excess(&mut self, reject_excess: bool) {
+        self.reject_excess = reject_excess;
+    }
+
+    /// Accepts next incoming connection, respecting the accept rate limit
+    pub fn accept(&mut self) -> Result<Connection, Error> {
+        loop {
+            // The limit is re-checked after each sleep, so zero limit never
+            // lets a connection through
+            while !self.reject_excess {
+                let delay = match self.throttle_delay(Instant::now()) {
+                    None => break,
+                    Some(delay) => delay,
+                };
+                #[cfg(feature = "tracing")]
+                tracing::debug!(?delay, "FTCP accept throttled");
+                thread::sleep(delay);
+            }
+            let (stream, remote_addr) = self.listener.accept()?;
+            let now = Instant::now();
+            if self.reject_excess && self.throttle_delay(now).is_some() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    remote = %remote_addr,
+                    "FTCP connection rejected due to accept rate limit"
+                );
+                // Dropping the stream closes the connection
+                continue;
+            }
+            if self.max_accept_rate.is_some() {
+                self.accepted.push_back(now);
+            }
+            // NB: This is how we handle ping-pong cycles
+            stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+            #[cfg(feature = "tracing")]
+            tracing::debug!(remote = %remote_addr, "FTCP accepted");
+            return Ok(Connection::with(stream, remote_addr.into()));
+        }
+    }
+
+    /// Returns time to wait before accepting a new connection under the rate
+    /// limit, or `None` if the connection can be accepted now
+    fn throttle_delay(&mut self, now: Instant) -> Option<Duration> {
+        let max_accept_rate = self.max_accept_rate? as usize;
+        while let Some(time) = self.accepted.front() {
+            if now.duration_since(*time) < ACCEPT_RATE_PERIOD {
+                break;
+            }
+            self.accepted.pop_front();
+        }
+        if self.accepted.len() < max_accept_rate {
+            return None;
+        }
+        Some(
+            self.accepted
+                .front()
+                .map(|time| *time + ACCEPT_RATE_PERIOD - now)
+                .unwrap_or(ACCEPT_RATE_PERIOD),
+        )
+    }
+}
+
+impl Stream {
+    /// Sends close frame with a given `reason` and shuts down the write side
+    /// of the stream. See [`Connection::send_close`] for the details.
+    pub fn send_close(&mut self, reason: &[u8]) -> Result<(), Error> {
+        let len = reason.len();
+        if len > MAX_FRAME_PAYLOAD_SIZE {
+            return Err(Error::OversizedFrame(
+                FRAME_PREFIX_SIZE + len + FRAME_SUFFIX_SIZE,
+            ));
+        }
+        let mut frame =
+            Vec::with_capacity(FRAME_PREFIX_SIZE + len + FRAME_SUFFIX_SIZE);
+        frame.extend_from_slice(&(len as u16).to_be_bytes());
+        frame.extend_from_slice(&[CLOSE_FRAME_TAG; FRAME_PREFIX_SIZE - 2]);
+        frame.extend_from_slice(reason);
+        frame.extend_from_slice(&[0u8; FRAME_SUFFIX_SIZE]);
+        self.0.send_frame(&frame)?;
+        self.0.shutdown(Shutdown::Write)?;
+        Ok(())
+    }
+
+    /// Reads and discards the next frame. See [`Connection::skip_frame`] for
+    /// the details.
+    pub fn skip_frame(&mut self) -> Result<usize, Error> {
+        let prefix = generic::read_prefix(&mut self.0)?;
+        let len = u16::from_be_bytes([prefix[0], prefix[1]]) as usize;
+        if prefix[2..].iter().all(|byte| *byte == CLOSE_FRAME_TAG) {
+            let mut reason = vec![0u8; len];
+            self.0.read_exact(&mut reason)?;
+            return Err(Error::ClosedByPeer(reason));
+        }
+        generic::discard_frame(&mut self.0, len)
     }
 }
 
@@ -75,8 +547,27 @@ impl Duplex for Stream {
 }
 
 impl RecvFrame for Stream {
-    #[inline]
-    fn recv_frame(&mut self) -> Result<Vec<u8>, Error> { self.0.recv_frame() }
+    /// Receives FTCP frame, returning [`Error::ClosedByPeer`] if the frame is
+    /// a close frame sent by [`Connection::send_close`]
+    fn recv_frame(&mut self) -> Result<Vec<u8>, Error> {
+        let mut frame = vec![];
+        self.recv_frame_into(&mut frame)?;
+        Ok(frame)
+    }
+
+    /// Receives FTCP frame directly into the buffer, returning
+    /// [`Error::ClosedByPeer`] if the frame is a close frame
+    fn recv_frame_into(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let len = self.0.recv_frame_into(buf)?;
+        if buf[2..FRAME_PREFIX_SIZE]
+            .iter()
+            .all(|byte| *byte == CLOSE_FRAME_TAG)
+        {
+            let reason = &buf[FRAME_PREFIX_SIZE..len - FRAME_SUFFIX_SIZE];
+            return Err(Error::ClosedByPeer(reason.to_vec()));
+        }
+        Ok(len)
+    }
 
     #[inline]
     fn recv_raw(&mut self, len: usize) -> Result<Vec<u8>, Error> {
@@ -85,8 +576,14 @@ impl RecvFrame for Stream {
 }
 
 impl SendFrame for Stream {
-    #[inline]
+    /// Sends FTCP frame, checking that the payload length declared in the
+    /// frame prefix matches the actual size of the frame payload.
     fn send_frame(&mut self, data: &[u8]) -> Result<usize, Error> {
+        let len = data.len();
+        if len > self.max_frame_size() {
+            return Err(Error::OversizedFrame(len));
+        }
+        validate_frame(data)?;
         self.0.send_frame(data)
     }
 
@@ -94,4 +591,570 @@ impl SendFrame for Stream {
     fn send_raw(&mut self, data: &[u8]) -> Result<usize, Error> {
         self.0.send_raw(data)
     }
+
+    /// Sends FTCP frame composed of several parts with a single vectored
+    /// write, performing the same checks as [`SendFrame::send_frame`]
+    fn send_frame_vectored(&mut self, parts: &[&[u8]]) -> Result<usize, Error> {
+        let len = parts.iter().map(|part| part.len()).sum::<usize>();
+        if len > self.max_frame_size() {
+            return Err(Error::OversizedFrame(len));
+        }
+        if len < FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE {
+            return Err(Error::FrameTooSmall(len));
+        }
+        // The length prefix itself may be split between the parts
+        let mut prefix = parts.iter().flat_map(|part| part.iter());
+        let declared = prefix
+            .next()
+            .zip(prefix.next())
+            .map(|(hi, lo)| u16::from_be_bytes([*hi, *lo]))
+            .expect("frame length is checked to contain the prefix");
+        let expected = (len - FRAME_PREFIX_SIZE - FRAME_SUFFIX_SIZE) as u16;
+        if declared != expected {
+            return Err(Error::InvalidLength {
+                expected,
+                actual: declared,
+            });
+        }
+        self.0.send_frame_vectored(parts)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use inet2_addr::AddrParseError;
+
+    use super::*;
+    use crate::transport::{empty_frame, socks5};
+
+    /// Establishes connection between two peers on a system-assigned port
+    fn connection_pair() -> (Connection, Connection) {
+        let mut listener =
+            Listener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let inet_addr = listener.local_addr().unwrap();
+        let rx = thread::spawn(move || listener.accept());
+        let connection = Connection::connect(inet_addr).unwrap();
+        (connection, rx.join().unwrap().unwrap())
+    }
+
+    fn negotiate(
+        versions_a: RangeInclusive<u16>,
+        versions_b: RangeInclusive<u16>,
+    ) -> (Result<u16, Error>, Result<u16, Error>) {
+        let (mut a, mut b) = connection_pair();
+        assert_eq!(a.negotiated_version(), None);
+        let b = thread::spawn(move || {
+            let res = b.negotiate_version(versions_b);
+            assert_eq!(b.negotiated_version(), res.clone().ok());
+            res
+        });
+        let res = a.negotiate_version(versions_a);
+        assert_eq!(a.negotiated_version(), res.clone().ok());
+        (res, b.join().unwrap())
+    }
+
+    #[test]
+    fn test_version_negotiation() {
+        assert_eq!(negotiate(1..=3, 2..=5), (Ok(3), Ok(3)));
+        assert_eq!(negotiate(1..=1, 1..=1), (Ok(1), Ok(1)));
+        assert!(matches!(
+            negotiate(1..=2, 3..=5),
+            (Err(Error::HandshakeFailed(_)), Err(Error::HandshakeFailed(_)))
+        ));
+    }
+
+    #[test]
+    fn test_connect_str() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        Connection::connect_str(&addr.to_string()).unwrap();
+        listener.accept().unwrap();
+
+        assert_eq!(
+            Connection::connect_str("127.0.0.1").unwrap_err(),
+            Error::AddrParse(AddrParseError::MissingPort(s!("127.0.0.1")))
+        );
+        assert!(matches!(
+            Connection::connect_str("not an address:9735"),
+            Err(Error::AddrParse(_))
+        ));
+    }
+
+    #[test]
+    fn test_exchange_magic() {
+        let (mut a, mut b) = connection_pair();
+        let b = thread::spawn(move || b.exchange_magic(LNP_MAGIC, 2));
+        assert_eq!(a.exchange_magic(LNP_MAGIC, 1), Ok(2));
+        assert_eq!(b.join().unwrap(), Ok(1));
+
+        let (mut a, mut b) = connection_pair();
+        let b = thread::spawn(move || b.exchange_magic(*b"HTTP", 1));
+        let err = Error::HandshakeFailed(s!("wrong protocol magic"));
+        assert_eq!(a.exchange_magic(LNP_MAGIC, 1), Err(err.clone()));
+        assert_eq!(b.join().unwrap(), Err(err));
+    }
+
+    /// Opens `count` connections to the listener as fast as possible
+    fn flood(
+        listener: &Listener,
+        count: usize,
+    ) -> thread::JoinHandle<Vec<Connection>> {
+        let inet_addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            (0..count)
+                .map(|_| Connection::connect(inet_addr).unwrap())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_accept_rate_throttle() {
+        let mut listener =
+            Listener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        listener.set_max_accept_rate(Some(2));
+        let peers = flood(&listener, 4);
+
+        let start = Instant::now();
+        listener.accept().unwrap();
+        listener.accept().unwrap();
+        assert!(start.elapsed() < ACCEPT_RATE_PERIOD);
+        listener.accept().unwrap();
+        listener.accept().unwrap();
+        assert!(start.elapsed() >= ACCEPT_RATE_PERIOD);
+        assert_eq!(peers.join().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_accept_rate_zero() {
+        let mut listener =
+            Listener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        listener.set_max_accept_rate(Some(0));
+        let now = Instant::now();
+        assert_eq!(listener.throttle_delay(now), Some(ACCEPT_RATE_PERIOD));
+        assert_eq!(
+            listener.throttle_delay(now + ACCEPT_RATE_PERIOD * 2),
+            Some(ACCEPT_RATE_PERIOD)
+        );
+    }
+
+    #[test]
+    fn test_accept_rate_reject() {
+        let mut listener =
+            Listener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        listener.set_max_accept_rate(Some(1));
+        listener.set_reject_excess(true);
+        let inet_addr = listener.local_addr().unwrap();
+        let peers = thread::spawn(move || {
+            let first = Connection::connect(inet_addr).unwrap();
+            let mut excess = Connection::connect(inet_addr).unwrap();
+            // Excessive connection must be closed by the listener
+            let closed = excess.as_receiver().recv_frame();
+            thread::sleep(ACCEPT_RATE_PERIOD);
+            let last = Connection::connect(inet_addr).unwrap();
+            (first, closed, last)
+        });
+
+        let start = Instant::now();
+        listener.accept().unwrap();
+        listener.accept().unwrap();
+        assert!(start.elapsed() >= ACCEPT_RATE_PERIOD);
+        let (_, closed, _) = peers.join().unwrap();
+        assert!(matches!(
+            closed,
+            Err(Error::SocketIo(std::io::ErrorKind::UnexpectedEof))
+                | Err(Error::ConnectionReset)
+        ));
+    }
+
+    #[test]
+    fn test_label() {
+        let (mut a, _b) = connection_pair();
+        assert_eq!(a.label(), None);
+        assert!(!format!("{:?}", a).contains("peer-alias"));
+        a.set_label("peer-alias");
+        assert_eq!(a.label(), Some("peer-alias"));
+        assert!(format!("{:?}", a).contains("peer-alias"));
+    }
+
+    #[test]
+    fn test_send_close() {
+        let (mut a, mut b) = connection_pair();
+
+        let frame = PlainTranscoder.encrypt(&b"Last message"[..]);
+        a.as_sender().send_frame(&frame).unwrap();
+        a.send_close(b"policy violation").unwrap();
+        assert_eq!(b.as_receiver().recv_frame(), Ok(frame.clone()));
+        assert_eq!(
+            b.as_receiver().recv_frame(),
+            Err(Error::ClosedByPeer(b"policy violation".to_vec()))
+        );
+        assert!(a.as_sender().send_frame(&frame).is_err());
+
+        // Only the write side of the closing peer is shut down
+        b.as_sender().send_frame(&frame).unwrap();
+        assert_eq!(a.as_receiver().recv_frame(), Ok(frame));
+    }
+
+    #[test]
+    fn test_skip_frame() {
+        let (mut a, mut b) = connection_pair();
+
+        let unwanted = PlainTranscoder.encrypt(vec![0xA5u8; 5000]);
+        let wanted = PlainTranscoder.encrypt(&b"Wanted message"[..]);
+        a.as_sender().send_frame(&unwanted).unwrap();
+        a.as_sender().send_frame(&wanted).unwrap();
+
+        let mut prefix = [0u8; 2];
+        while b.stream.0.peek(&mut prefix).unwrap() < prefix.len() {}
+        assert_eq!(u16::from_be_bytes(prefix), 5000);
+        assert_eq!(b.skip_frame(), Ok(5000));
+        assert_eq!(b.as_receiver().recv_frame(), Ok(wanted));
+
+        a.send_close(b"bye").unwrap();
+        assert_eq!(b.skip_frame(), Err(Error::ClosedByPeer(b"bye".to_vec())));
+    }
+
+    #[test]
+    fn test_empty_frame() {
+        let (mut a, mut b) = connection_pair();
+        a.as_sender().send_frame(&empty_frame()).unwrap();
+        let frame = b.as_receiver().recv_frame().unwrap();
+        assert_eq!(validate_frame(&frame), Ok(&[][..]));
+    }
+
+    #[test]
+    fn test_routed_unsupported() {
+        let (mut a, mut b) = connection_pair();
+        assert_eq!(
+            a.as_sender().send_routed(b"src", b"route", b"dst", b"data"),
+            Err(Error::MultipeerUnsupported)
+        );
+        assert_eq!(
+            b.as_receiver().recv_routed(),
+            Err(Error::MultipeerUnsupported)
+        );
+
+        // The connection remains usable for non-routed frames
+        let frame = PlainTranscoder.encrypt(&b"Some message"[..]);
+        a.as_sender().send_frame(&frame).unwrap();
+        assert_eq!(b.as_receiver().recv_frame(), Ok(frame));
+    }
+
+    #[test]
+    fn test_recv_frame_into() {
+        let (mut a, mut b) = connection_pair();
+
+        let frames = (0..256usize)
+            .map(|n| PlainTranscoder.encrypt(vec![n as u8; (n * 37) % 1000]))
+            .collect::<Vec<_>>();
+        let sender = thread::spawn(move || {
+            for frame in &frames {
+                a.as_sender().send_frame(frame).unwrap();
+            }
+            a.send_close(b"done").unwrap();
+            frames
+        });
+
+        let mut buf = Vec::with_capacity(crate::transport::MAX_FRAME_SIZE);
+        let ptr = buf.as_ptr();
+        let mut received = 0usize;
+        let frames = loop {
+            match b.as_receiver().recv_frame_into(&mut buf) {
+                Ok(len) => {
+                    assert_eq!(len, buf.len());
+                    let payload = validate_frame(&buf).unwrap();
+                    let expected = received as u8;
+                    assert!(payload.iter().all(|byte| *byte == expected));
+                    received += 1;
+                }
+                Err(err) => {
+                    assert_eq!(err, Error::ClosedByPeer(b"done".to_vec()));
+                    break sender.join().unwrap();
+                }
+            }
+        };
+        assert_eq!(received, frames.len());
+        // The buffer was never re-allocated
+        assert_eq!(buf.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_send_frame_vectored() {
+        let (mut a, mut b) = connection_pair();
+
+        let frame = PlainTranscoder.encrypt(&b"Vectored message"[..]);
+        let (prefix, rest) = frame.split_at(1);
+        let (payload, suffix) = rest.split_at(FRAME_PREFIX_SIZE + 7);
+        let parts = [prefix, &[][..], payload, suffix];
+        assert_eq!(a.as_sender().send_frame_vectored(&parts), Ok(frame.len()));
+        assert_eq!(b.as_receiver().recv_frame(), Ok(frame.clone()));
+
+        // Default implementation produces the same bytes on the wire
+        let mut concat = MemSender::default();
+        concat.send_frame_vectored(&parts).unwrap();
+        assert_eq!(concat.0, frame);
+
+        let short = &frame[..frame.len() - 1];
+        assert_eq!(
+            a.as_sender().send_frame_vectored(&[short]),
+            Err(Error::InvalidLength {
+                expected: 15,
+                actual: 16
+            })
+        );
+        assert_eq!(
+            a.as_sender().send_frame_vectored(&[&frame[..10]]),
+            Err(Error::FrameTooSmall(10))
+        );
+        let oversized = vec![0u8; MAX_FRAME_PAYLOAD_SIZE];
+        assert!(matches!(
+            a.as_sender().send_frame_vectored(&[&frame, &oversized]),
+            Err(Error::OversizedFrame(_))
+        ));
+    }
+
+    /// Sender collecting all the frames into a single byte string
+    #[derive(Default)]
+    struct MemSender(Vec<u8>);
+
+    impl SendFrame for MemSender {
+        fn send_frame(&mut self, frame: &[u8]) -> Result<usize, Error> {
+            self.0.extend_from_slice(frame);
+            Ok(frame.len())
+        }
+
+        fn send_raw(&mut self, raw_frame: &[u8]) -> Result<usize, Error> {
+            self.send_frame(raw_frame)
+        }
+    }
+
+    /// Accepts a single connection acting as SOCKS5 proxy which connects
+    /// the client to itself, returning the received `CONNECT` request and the
+    /// connection to the client
+    fn socks5_proxy(
+        reply: u8,
+    ) -> (InetSocketAddr, thread::JoinHandle<(Vec<u8>, Connection)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = listener.local_addr().unwrap().into();
+        let handle = thread::spawn(move || {
+            let (mut stream, remote) = listener.accept().unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            stream.write_all(&[5, 0]).unwrap();
+
+            let mut request = vec![0u8; 5];
+            stream.read_exact(&mut request).unwrap();
+            let len = match request[3] {
+                1 => 4 + 4 + 2,
+                3 => 4 + 1 + request[4] as usize + 2,
+                _ => 4 + 16 + 2,
+            };
+            request.resize(len, 0);
+            stream.read_exact(&mut request[5..]).unwrap();
+            stream.write_all(&[5, reply, 0, 1, 127, 0, 0, 1, 0, 0]).unwrap();
+            (request, Connection::with(stream, remote.into()))
+        });
+        (proxy, handle)
+    }
+
+    #[test]
+    fn test_connect_socks5() {
+        let remote = InetSocketAddr::new([10, 0, 0, 1].into(), 9735);
+        let (proxy, handle) = socks5_proxy(0);
+        let mut a = Connection::connect_socks5(remote, proxy).unwrap();
+        let (request, mut b) = handle.join().unwrap();
+        assert_eq!(request, [5, 1, 0, 1, 10, 0, 0, 1, 0x26, 0x07]);
+
+        let frame = PlainTranscoder.encrypt(&b"Through proxy"[..]);
+        a.as_sender().send_frame(&frame).unwrap();
+        assert_eq!(b.as_receiver().recv_frame(), Ok(frame));
+
+        let (proxy, handle) = socks5_proxy(4);
+        assert_eq!(
+            Connection::connect_socks5(remote, proxy).err(),
+            Some(Error::Socks5(socks5::Error::HostUnreachable))
+        );
+        handle.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "tor")]
+    fn test_connect_socks5_onion() {
+        let onion = "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd";
+        let remote = InetSocketAddr::new(onion.parse().unwrap(), 9735);
+        let (proxy, handle) = socks5_proxy(0);
+        Connection::connect_socks5(remote, proxy).unwrap();
+        let (request, _) = handle.join().unwrap();
+        assert_eq!(request[..5], [5, 1, 0, 3, 62]);
+        assert_eq!(&request[5..67], format!("{}.onion", onion).as_bytes());
+        assert_eq!(request[67..], [0x26, 0x07]);
+    }
+
+    /// Connects to a real onion service through the Tor daemon; runs only if
+    /// `INET2_TEST_TOR_ONION` environment variable contains `<onion>:<port>`
+    /// address of a reachable onion service
+    #[test]
+    #[cfg(feature = "tor")]
+    fn test_connect_tor() {
+        let addr = match std::env::var("INET2_TEST_TOR_ONION") {
+            Ok(addr) => addr,
+            Err(_) => return,
+        };
+        let remote = InetSocketAddr::from_str_port_required(&addr).unwrap();
+        Connection::connect(remote).unwrap();
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let (mut a, mut b) = connection_pair();
+        assert!(a.read_timeout().unwrap().is_some());
+        assert_eq!(a.write_timeout(), Ok(None));
+
+        let timeout = Duration::from_millis(50);
+        a.set_read_timeout(Some(timeout)).unwrap();
+        a.set_write_timeout(Some(timeout)).unwrap();
+        // OS may round the timeout up to the scheduler tick
+        assert!(a.read_timeout().unwrap().unwrap() >= timeout);
+        assert!(a.write_timeout().unwrap().unwrap() >= timeout);
+        assert_eq!(
+            a.set_read_timeout(Some(Duration::ZERO)),
+            Err(Error::SocketIo(std::io::ErrorKind::InvalidInput))
+        );
+
+        let start = Instant::now();
+        assert_eq!(a.as_receiver().recv_frame(), Err(Error::TimedOut));
+        assert!(start.elapsed() >= timeout);
+
+        // The connection remains usable after the timeout
+        let frame = PlainTranscoder.encrypt(&b"Late message"[..]);
+        b.as_sender().send_frame(&frame).unwrap();
+        assert_eq!(a.as_receiver().recv_frame(), Ok(frame));
+
+        a.set_read_timeout(None).unwrap();
+        assert_eq!(a.read_timeout(), Ok(None));
+    }
+
+    #[test]
+    fn test_frame_length_mismatch() {
+        let (mut a, mut b) = connection_pair();
+
+        let mut frame = PlainTranscoder.encrypt(&b"Some message"[..]);
+        frame[1] += 1;
+        assert_eq!(
+            a.as_sender().send_frame(&frame),
+            Err(Error::InvalidLength {
+                expected: 12,
+                actual: 13
+            })
+        );
+        frame[1] -= 2;
+        assert_eq!(
+            a.as_sender().send_frame(&frame),
+            Err(Error::InvalidLength {
+                expected: 12,
+                actual: 11
+            })
+        );
+        assert_eq!(
+            a.as_sender().send_frame(&frame[..20]),
+            Err(Error::FrameTooSmall(20))
+        );
+
+        // Nothing was sent by the failed calls, so the peer receives exactly
+        // the frame with a consistent length prefix
+        frame[1] += 1;
+        assert_eq!(a.as_sender().send_frame(&frame), Ok(frame.len()));
+        assert_eq!(b.as_receiver().recv_frame(), Ok(frame));
+    }
+
+    /// Subscriber recording all events as `name=value` lines
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct EventRecorder(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[cfg(feature = "tracing")]
+    impl EventRecorder {
+        fn contains(&self, s: &str) -> bool {
+            self.0.lock().unwrap().iter().any(|line| line.contains(s))
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for EventRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool { true }
+
+        fn new_span(
+            &self,
+            _: &tracing::span::Attributes<'_>,
+        ) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+
+        fn record(
+            &self,
+            _: &tracing::span::Id,
+            _: &tracing::span::Record<'_>,
+        ) {
+        }
+
+        fn record_follows_from(
+            &self,
+            _: &tracing::span::Id,
+            _: &tracing::span::Id,
+        ) {
+        }
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            struct Visitor(String);
+            impl tracing::field::Visit for Visitor {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    self.0 += &format!(" {}={:?}", field.name(), value);
+                }
+            }
+            let mut visitor = Visitor(String::new());
+            event.record(&mut visitor);
+            self.0.lock().unwrap().push(visitor.0);
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn test_tracing_events() {
+        let mut listener =
+            Listener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let inet_addr = listener.local_addr().unwrap();
+
+        // Events from the other thread must go to the same subscriber
+        let recorder = EventRecorder::default();
+        let dispatch = tracing::Dispatch::new(recorder.clone());
+        let _guard = tracing::dispatcher::set_default(&dispatch);
+        let rx = thread::spawn(move || {
+            tracing::dispatcher::with_default(&dispatch, || {
+                let mut connection = listener.accept().unwrap();
+                connection.as_receiver().recv_frame().unwrap()
+            })
+        });
+        let mut connection = Connection::connect(inet_addr).unwrap();
+        let frame = [0u8; 34];
+        connection.as_sender().send_frame(&frame).unwrap();
+        assert_eq!(rx.join().unwrap(), frame);
+
+        assert!(recorder.contains("FTCP connected"));
+        assert!(recorder.contains("FTCP accepted"));
+        assert!(recorder.contains("frame sent"));
+        assert!(recorder.contains("frame received"));
+        assert!(recorder.contains("size=34"));
+        assert!(recorder.contains(&format!("remote={}", inet_addr)));
+    }
 }
@@ -14,14 +14,22 @@
 //! Types generic over specific implementations
 
 use std::convert::TryFrom;
-use std::io::{Read, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::io::{self, IoSlice, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
 use std::time::Duration;
 
 use amplify::Bipolar;
-use inet2_addr::InetSocketAddr;
+use inet2_addr::{InetAddr, InetSocketAddr};
+use socket2::{Domain, Socket, Type};
 
-use crate::transport::{Error, RecvFrame, SendFrame};
+use crate::session::{Decrypt, Encrypt, PlainTranscoder};
+use crate::transport::{
+    socks5, Error, RecvFrame, SendFrame, FRAME_PREFIX_SIZE, FRAME_SUFFIX_SIZE,
+    MAX_FRAME_PAYLOAD_SIZE,
+};
 use crate::Duplex;
 
 /// A market trait for concrete stream implementations which can be used as a
@@ -34,8 +42,13 @@ pub trait Stream: RecvFrame + SendFrame + From<TcpStream> {}
 /// This connection type is used by FTCP and Brontide protocols.
 #[derive(Debug)]
 pub struct Connection<S: Stream> {
-    pub(self) stream: S,
+    pub(super) stream: S,
     pub(self) remote_addr: InetSocketAddr,
+    /// Protocol version agreed with the remote peer, if the version
+    /// negotiation was performed
+    pub(super) negotiated_version: Option<u16>,
+    /// Human-readable label of the connection used for log correlation
+    pub(super) label: Option<String>,
 }
 
 impl<S: Stream> Connection<S> {
@@ -43,8 +56,27 @@ impl<S: Stream> Connection<S> {
         Self {
             stream: S::from(stream),
             remote_addr,
+            negotiated_version: None,
+            label: None,
         }
     }
+
+    /// Assigns human-readable label to the connection (for instance, alias of
+    /// the remote peer). The label is included into the `Debug` output and
+    /// into the tracing events of FTCP magic and version negotiation,
+    /// simplifying correlation of log records in daemons handling many
+    /// connections. Connection establishment and frame events are emitted
+    /// before the label is assigned or by the underlying stream, so they
+    /// identify the connection by the remote address instead.
+    #[inline]
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = Some(label.into())
+    }
+
+    /// Returns label assigned to the connection with
+    /// [`Connection::set_label`], if any
+    #[inline]
+    pub fn label(&self) -> Option<&str> { self.label.as_deref() }
 }
 
 impl<S: Stream + Duplex> Duplex for Connection<S> {
@@ -60,6 +92,8 @@ impl<S: Stream + Duplex> Duplex for Connection<S> {
     fn split(self) -> (Box<dyn RecvFrame + Send>, Box<dyn SendFrame + Send>) {
         self.stream.split()
     }
+    #[inline]
+    fn max_frame_size(&self) -> usize { self.stream.max_frame_size() }
 }
 
 impl<S: Stream + Bipolar<Left = S, Right = S>> Bipolar for Connection<S> {
@@ -71,17 +105,493 @@ impl<S: Stream + Bipolar<Left = S, Right = S>> Bipolar for Connection<S> {
             stream: S::join(left, right),
             // TODO: (v1) Replace with remote address
             remote_addr: Default::default(),
+            negotiated_version: None,
+            label: None,
         }
     }
 
     fn split(self) -> (Self::Left, Self::Right) { self.stream.split() }
 }
 
+/// Adapter providing [`io::Read`] and [`io::Write`] APIs over some [`Duplex`]
+/// for legacy code which is able to work only with plain byte streams.
+///
+/// NB: the adapter re-frames byte stream and is not zero-overhead: each
+/// `write` call packs data into a new unencrypted frame, and each received
+/// frame is unpacked and buffered until its data are completely consumed by
+/// `read` calls.
+pub struct DuplexIo<D: Duplex> {
+    duplex: D,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+impl<D: Duplex> DuplexIo<D> {
+    /// Wraps given [`Duplex`] into the adapter
+    #[inline]
+    pub fn with(duplex: D) -> Self {
+        Self {
+            duplex,
+            buffer: vec![],
+            pos: 0,
+        }
+    }
+
+    /// Releases inner [`Duplex`] discarding all buffered data which were not
+    /// consumed yet
+    #[inline]
+    pub fn into_inner(self) -> D { self.duplex }
+}
+
+impl<D: Duplex> Read for DuplexIo<D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // Empty frames do not carry any data and must not be reported as
+        // the end of the stream
+        while self.pos >= self.buffer.len() {
+            let frame = self.duplex.as_receiver().recv_frame()?;
+            self.buffer = PlainTranscoder.decrypt(frame)?;
+            self.pos = 0;
+        }
+        let len = buf.len().min(self.buffer.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl<D: Duplex> Write for DuplexIo<D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(MAX_FRAME_PAYLOAD_SIZE);
+        let frame = PlainTranscoder.encrypt(&buf[..len]);
+        self.duplex.as_sender().send_frame(&frame)?;
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Size of the scratch buffer used for discarding skipped frames
+const DISCARD_BUFFER_SIZE: usize = 1024;
+
+/// Size of the message continuation header prepended to each of the frames
+/// produced by [`MessageIo`]
+const MESSAGE_HEADER_SIZE: usize = 1;
+
+/// Continuation header flag marking the first frame of a message
+const MESSAGE_FIRST: u8 = 0x01;
+
+/// Continuation header flag marking the last frame of a message
+const MESSAGE_LAST: u8 = 0x02;
+
+/// Adapter sending and receiving messages of arbitrary size over some
+/// [`Duplex`], splitting them into multiple unencrypted frames when they do
+/// not fit into a single one.
+///
+/// Each frame starts with a one-byte continuation header, carrying
+/// "first" (`0x01`) and "last" (`0x02`) flags: a message which fits into a
+/// single frame has both flags set, and frames in the middle of a message
+/// have none of them.
+pub struct MessageIo<D: Duplex> {
+    duplex: D,
+    max_message_size: usize,
+}
+
+impl<D: Duplex> MessageIo<D> {
+    /// Wraps given [`Duplex`] into the adapter, which will refuse to receive
+    /// messages larger than `max_message_size` bytes
+    #[inline]
+    pub fn with(duplex: D, max_message_size: usize) -> Self {
+        Self {
+            duplex,
+            max_message_size,
+        }
+    }
+
+    /// Returns maximum size of a message which can be received
+    #[inline]
+    pub fn max_message_size(&self) -> usize { self.max_message_size }
+
+    /// Releases inner [`Duplex`]
+    #[inline]
+    pub fn into_inner(self) -> D { self.duplex }
+
+    /// Sends the payload as a single message, splitting it into as many
+    /// frames as required. Empty payload is sent as a single empty frame.
+    pub fn send_message(&mut self, payload: &[u8]) -> Result<(), Error> {
+        let chunk_size = MAX_FRAME_PAYLOAD_SIZE - MESSAGE_HEADER_SIZE;
+        let count = ((payload.len() + chunk_size - 1) / chunk_size).max(1);
+        let mut chunks = payload.chunks(chunk_size);
+        for no in 0..count {
+            let chunk = chunks.next().unwrap_or_default();
+            let mut flags = 0u8;
+            if no == 0 {
+                flags |= MESSAGE_FIRST;
+            }
+            if no == count - 1 {
+                flags |= MESSAGE_LAST;
+            }
+            let mut data =
+                Vec::with_capacity(MESSAGE_HEADER_SIZE + chunk.len());
+            data.push(flags);
+            data.extend_from_slice(chunk);
+            let frame = PlainTranscoder.encrypt(data);
+            self.duplex.as_sender().send_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    /// Receives frames until a complete message is assembled.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::OversizedMessage`] if the message exceeds
+    /// [`MessageIo::max_message_size`] and with [`Error::FrameBroken`] if
+    /// the continuation headers are missed or inconsistent. In both cases
+    /// the rest of the message frames are left unread, so the connection
+    /// should be dropped.
+    pub fn recv_message(&mut self) -> Result<Vec<u8>, Error> {
+        let mut message = vec![];
+        let mut started = false;
+        loop {
+            let frame = self.duplex.as_receiver().recv_frame()?;
+            let data = PlainTranscoder.decrypt(frame)?;
+            let (flags, chunk) = data.split_first().ok_or(
+                Error::FrameBroken("message frame lacks continuation header"),
+            )?;
+            if started == (flags & MESSAGE_FIRST != 0) {
+                return Err(Error::FrameBroken(
+                    "message frames are out of order",
+                ));
+            }
+            started = true;
+            let size = message.len() + chunk.len();
+            if size > self.max_message_size {
+                return Err(Error::OversizedMessage {
+                    size,
+                    max: self.max_message_size,
+                });
+            }
+            message.extend_from_slice(chunk);
+            if flags & MESSAGE_LAST != 0 {
+                return Ok(message);
+            }
+        }
+    }
+}
+
+/// Memory budget limiting total size of the frames which were received, but
+/// not consumed yet by the application. The budget may be cloned and shared
+/// between multiple connections, limiting memory used by all of them.
+#[derive(Clone, Debug)]
+pub struct MemoryBudget {
+    limit: usize,
+    used: Arc<AtomicUsize>,
+}
+
+impl MemoryBudget {
+    /// Constructs new budget allowing to hold up to `limit` bytes
+    #[inline]
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit,
+            used: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns maximum number of bytes which can be held under the budget
+    #[inline]
+    pub fn limit(&self) -> usize { self.limit }
+
+    /// Returns number of bytes currently held under the budget
+    #[inline]
+    pub fn used(&self) -> usize { self.used.load(Ordering::SeqCst) }
+
+    /// Returns number of bytes which can be allocated under the budget
+    #[inline]
+    pub fn available(&self) -> usize { self.limit.saturating_sub(self.used()) }
+
+    /// Returns `len` bytes back to the budget. Must be called by the
+    /// application once it has consumed the frame received via
+    /// [`BudgetedRecv`].
+    pub fn release(&self, len: usize) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                Some(used.saturating_sub(len))
+            });
+    }
+
+    fn reserve(&self, len: usize) -> Result<(), Error> {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                used.checked_add(len).filter(|total| *total <= self.limit)
+            })
+            .map(|_| ())
+            .map_err(|used| Error::MemoryLimit {
+                requested: len,
+                available: self.limit.saturating_sub(used),
+            })
+    }
+}
+
+/// Frame receiver accounting size of all received frames against some
+/// [`MemoryBudget`].
+///
+/// If the frame does not fit into the budget, it is kept inside the receiver
+/// and [`Error::MemoryLimit`] is returned; no new frames are read until the
+/// application releases enough memory with [`MemoryBudget::release`], after
+/// which the kept frame is returned by the next call.
+#[derive(Debug)]
+pub struct BudgetedRecv<R: RecvFrame> {
+    receiver: R,
+    budget: MemoryBudget,
+    pending: Option<Vec<u8>>,
+}
+
+impl<R: RecvFrame> BudgetedRecv<R> {
+    /// Wraps frame receiver, accounting received frames with `budget`
+    #[inline]
+    pub fn with(receiver: R, budget: MemoryBudget) -> Self {
+        Self {
+            receiver,
+            budget,
+            pending: None,
+        }
+    }
+
+    /// Returns memory budget used by the receiver
+    #[inline]
+    pub fn budget(&self) -> &MemoryBudget { &self.budget }
+
+    /// Releases inner frame receiver discarding the frame which was kept due
+    /// to the budget limit, if any
+    #[inline]
+    pub fn into_inner(self) -> R { self.receiver }
+
+    fn accounted(&mut self, frame: Vec<u8>) -> Result<Vec<u8>, Error> {
+        match self.budget.reserve(frame.len()) {
+            Ok(()) => Ok(frame),
+            Err(err) => {
+                self.pending = Some(frame);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<R: RecvFrame> RecvFrame for BudgetedRecv<R> {
+    fn recv_frame(&mut self) -> Result<Vec<u8>, Error> {
+        let frame = match self.pending.take() {
+            Some(frame) => frame,
+            None => self.receiver.recv_frame()?,
+        };
+        self.accounted(frame)
+    }
+
+    fn recv_raw(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        let frame = match self.pending.take() {
+            Some(frame) => frame,
+            None => self.receiver.recv_raw(len)?,
+        };
+        self.accounted(frame)
+    }
+}
+
+/// Frame receiver enforcing timeout on the receiving operations regardless of
+/// whether the wrapped receiver supports timeouts on its own (for instance,
+/// when it is an in-memory channel or some other non-socket implementation).
+///
+/// Each of the receiving operations is run in a newly spawned worker thread,
+/// so the wrapper costs a thread spawn per call and is not suitable for the
+/// high-throughput use; prefer socket-level timeouts where they are
+/// available. If the operation has not completed in time,
+/// [`Error::TimedOut`] is returned and the worker keeps waiting; its result
+/// is returned by the next call, so no frames are lost.
+#[derive(Debug)]
+pub struct TimeoutRecv<R: RecvFrame + Send + 'static> {
+    receiver: Option<R>,
+    timeout: Duration,
+    pending: Option<mpsc::Receiver<TimeoutRecvResult<R>>>,
+}
+
+/// Receiver returned by the [`TimeoutRecv`] worker thread together with the
+/// result of the operation
+type TimeoutRecvResult<R> = (R, Result<Vec<u8>, Error>);
+
+impl<R: RecvFrame + Send + 'static> TimeoutRecv<R> {
+    /// Wraps frame receiver, limiting duration of each receive operation to
+    /// `timeout`
+    #[inline]
+    pub fn with(receiver: R, timeout: Duration) -> Self {
+        Self {
+            receiver: Some(receiver),
+            timeout,
+            pending: None,
+        }
+    }
+
+    /// Returns timeout applied to the receive operations
+    #[inline]
+    pub fn timeout(&self) -> Duration { self.timeout }
+
+    /// Sets timeout applied to the receive operations
+    #[inline]
+    pub fn set_timeout(&mut self, timeout: Duration) { self.timeout = timeout }
+
+    /// Detects whether some receive operation has timed out and is still
+    /// running in the worker thread
+    #[inline]
+    pub fn is_pending(&self) -> bool { self.pending.is_some() }
+
+    /// Releases inner frame receiver, unless it is still used by a timed out
+    /// receive operation (see [`TimeoutRecv::is_pending`])
+    #[inline]
+    pub fn into_inner(self) -> Option<R> { self.receiver }
+
+    fn run(
+        &mut self,
+        op: impl FnOnce(&mut R) -> Result<Vec<u8>, Error> + Send + 'static,
+    ) -> Result<Vec<u8>, Error> {
+        if let Some(mut receiver) = self.receiver.take() {
+            let (tx, rx) = mpsc::sync_channel(1);
+            thread::spawn(move || {
+                let res = op(&mut receiver);
+                let _ = tx.send((receiver, res));
+            });
+            self.pending = Some(rx);
+        }
+        let pending = self
+            .pending
+            .as_ref()
+            .expect("receiver is either idle or used by a pending operation");
+        match pending.recv_timeout(self.timeout) {
+            Ok((receiver, res)) => {
+                self.receiver = Some(receiver);
+                self.pending = None;
+                res
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(Error::TimedOut),
+            // The worker thread has panicked, so the receiver is lost
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(Error::SocketIo(io::ErrorKind::BrokenPipe))
+            }
+        }
+    }
+}
+
+impl<R: RecvFrame + Send + 'static> RecvFrame for TimeoutRecv<R> {
+    #[inline]
+    fn recv_frame(&mut self) -> Result<Vec<u8>, Error> {
+        self.run(R::recv_frame)
+    }
+
+    #[inline]
+    fn recv_raw(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        self.run(move |receiver| receiver.recv_raw(len))
+    }
+}
+
+/// Frame reader which reads frames into an internal buffer reused across the
+/// calls, providing zero-allocation read path for the single-threaded
+/// consumers which process each of the frames before reading the next one.
+///
+/// The buffer grows up to the size of the largest received frame and is
+/// never shrunk.
+#[derive(Debug)]
+pub struct FrameReader<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// Constructs frame reader reading frames from `reader`
+    #[inline]
+    pub fn with(reader: R) -> Self { Self { reader, buf: vec![] } }
+
+    /// Releases inner reader
+    #[inline]
+    pub fn into_inner(self) -> R { self.reader }
+
+    /// Receives a single frame, including its prefix and suffix, returning
+    /// slice of the internal buffer which remains valid until the next call.
+    ///
+    /// # Errors
+    /// Same as for [`RecvFrame::recv_frame`] implementation for
+    /// [`TcpStream`]; if the reader is closed at the frame boundary
+    /// [`io::ErrorKind::UnexpectedEof`] I/O error is returned.
+    pub fn recv_frame_ref(&mut self) -> Result<&[u8], Error> {
+        let prefix = read_prefix(&mut self.reader)?;
+        let len = u16::from_be_bytes([prefix[0], prefix[1]]) as usize;
+        // Does not re-allocate if the buffer has enough capacity
+        self.buf.resize(len + FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE, 0u8);
+        self.buf[..FRAME_PREFIX_SIZE].copy_from_slice(&prefix);
+        self.reader.read_exact(&mut self.buf[FRAME_PREFIX_SIZE..])?;
+        Ok(&self.buf)
+    }
+    /// Reads the next frame and discards it without copying it into the
+    /// internal buffer, returning the size of the skipped frame payload.
+    pub fn skip_frame(&mut self) -> Result<usize, Error> {
+        let prefix = read_prefix(&mut self.reader)?;
+        let len = u16::from_be_bytes([prefix[0], prefix[1]]) as usize;
+        discard_frame(&mut self.reader, len)
+    }
+}
+
+/// Performs SOCKS5 handshake over the stream connected to SOCKS5 proxy (like
+/// Tor daemon), requesting the proxy to connect to the `remote` address.
+/// Onion addresses are sent to the proxy in their string form, so they are
+/// resolved by Tor. Once the function returns, the stream is connected to the
+/// remote peer through the proxy.
+///
+/// # Errors
+/// * [`Error::Socks5`] if the proxy has rejected the connection or replied
+///   with a malformed message
+/// * [`Error::SocketIo`] and other I/O errors if the stream fails
+pub fn socks5_connect(
+    stream: &mut (impl Read + Write),
+    remote: InetSocketAddr,
+) -> Result<(), Error> {
+    stream.write_all(&socks5::SOCKS5_GREETING)?;
+    let mut method = [0u8; 2];
+    stream.read_exact(&mut method)?;
+    socks5::parse_socks5_method(&method)?;
+
+    stream.write_all(&socks5::encode_socks5_connect(
+        remote.address,
+        remote.port,
+    ))?;
+    let mut reply = vec![0u8; socks5::SOCKS5_REPLY_HEAD_SIZE];
+    stream.read_exact(&mut reply)?;
+    reply.resize(socks5::socks5_reply_len(&reply)?, 0u8);
+    stream.read_exact(&mut reply[socks5::SOCKS5_REPLY_HEAD_SIZE..])?;
+    socks5::parse_socks5_reply(&reply)?;
+    Ok(())
+}
+
 /// Extensions trait for simplifying [`TcpStream`] API in working with
 /// [`InetSocketAddr`] sockets
 pub trait TcpInetStream: Sized {
     fn connect_inet_socket(inet_addr: InetSocketAddr) -> Result<Self, Error>;
 
+    /// Connects to the remote socket binding local side of the connection to
+    /// the `bind_source` address (with a system-selected port), if provided.
+    /// Source address must be of the same family as the destination address.
+    fn connect_inet_socket_from(
+        inet_addr: InetSocketAddr,
+        bind_source: Option<InetAddr>,
+    ) -> Result<Self, Error>;
+
+    /// Connects to the remote socket failing with [`Error::TimedOut`] if the
+    /// connection was not established within the `timeout`
+    fn connect_inet_socket_timeout(
+        inet_addr: InetSocketAddr,
+        timeout: Duration,
+    ) -> Result<Self, Error>;
+
     fn accept_inet_socket(inet_addr: InetSocketAddr) -> Result<Self, Error>;
 
     fn join(left: Self, right: Self) -> Self;
@@ -101,6 +611,42 @@ impl TcpInetStream for TcpStream {
         }
     }
 
+    fn connect_inet_socket_from(
+        inet_addr: InetSocketAddr,
+        bind_source: Option<InetAddr>,
+    ) -> Result<Self, Error> {
+        let source = match bind_source {
+            None => return Self::connect_inet_socket(inet_addr),
+            Some(source) => source,
+        };
+        let dest = SocketAddr::try_from(inet_addr)
+            .map_err(|_| Error::TorNotSupportedYet)?;
+        let source = match IpAddr::try_from(source) {
+            Ok(ip) if ip.is_ipv4() == dest.is_ipv4() => SocketAddr::new(ip, 0),
+            Ok(_) => return Err(Error::SourceFamilyMismatch),
+            Err(_) => return Err(Error::TorNotSupportedYet),
+        };
+        let stream = connect_bound(source, dest)?;
+        // NB: This is how we handle ping-pong cycles
+        stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+        Ok(stream)
+    }
+
+    fn connect_inet_socket_timeout(
+        inet_addr: InetSocketAddr,
+        timeout: Duration,
+    ) -> Result<Self, Error> {
+        let socket_addr = SocketAddr::try_from(inet_addr)
+            .map_err(|_| Error::TorNotSupportedYet)?;
+        // Standard library completes non-blocking connect by polling the
+        // socket for writability and reading `SO_ERROR`, so the socket is
+        // never returned in a half-connected state
+        let stream = TcpStream::connect_timeout(&socket_addr, timeout)?;
+        // NB: This is how we handle ping-pong cycles
+        stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+        Ok(stream)
+    }
+
     fn accept_inet_socket(inet_addr: InetSocketAddr) -> Result<Self, Error> {
         if let Ok(socket_addr) = SocketAddr::try_from(inet_addr) {
             let listener = TcpListener::bind(socket_addr)?;
@@ -140,21 +686,85 @@ impl TcpInetStream for TcpStream {
     }
 }
 
+/// Creates TCP socket bound to the `source` address and connects it to the
+/// `dest` address. Rust standard library does not provide bind-before-connect
+/// API, so we use `socket2` for it.
+fn connect_bound(
+    source: SocketAddr,
+    dest: SocketAddr,
+) -> io::Result<TcpStream> {
+    let socket = Socket::new(Domain::for_address(dest), Type::STREAM, None)?;
+    socket.bind(&source.into())?;
+    socket.connect(&dest.into())?;
+    Ok(socket.into())
+}
+
+/// Reads frame prefix from the stream, distinguishing stream closed at the
+/// frame boundary (reported as [`io::ErrorKind::UnexpectedEof`] I/O error)
+/// from the stream closed in the middle of the prefix (reported as
+/// [`Error::FrameBroken`]).
+pub(super) fn read_prefix(
+    reader: &mut impl Read,
+) -> Result<[u8; FRAME_PREFIX_SIZE], Error> {
+    let mut prefix = [0u8; FRAME_PREFIX_SIZE];
+    let mut pos = 0;
+    while pos < FRAME_PREFIX_SIZE {
+        match reader.read(&mut prefix[pos..]) {
+            Ok(0) if pos == 0 => {
+                return Err(io::ErrorKind::UnexpectedEof.into())
+            }
+            Ok(0) => return Err(Error::FrameBroken("incomplete prefix")),
+            Ok(len) => pos += len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(prefix)
+}
+
+/// Reads and discards frame payload of `len` bytes together with the frame
+/// suffix, using a small fixed-size scratch buffer instead of allocating
+/// memory for the whole frame. Returns `len`.
+pub(super) fn discard_frame(
+    reader: &mut impl Read,
+    len: usize,
+) -> Result<usize, Error> {
+    let mut scratch = [0u8; DISCARD_BUFFER_SIZE];
+    let mut remaining = len + FRAME_SUFFIX_SIZE;
+    while remaining > 0 {
+        let chunk = remaining.min(DISCARD_BUFFER_SIZE);
+        reader.read_exact(&mut scratch[..chunk])?;
+        remaining -= chunk;
+    }
+    Ok(len)
+}
+
+// Frame events of the TCP stream are identified by the remote address: the
+// stream does not know the label of the `Connection` owning it, since the
+// connection hands out the stream itself as its receiver and sender
 impl RecvFrame for TcpStream {
     fn recv_frame(&mut self) -> Result<Vec<u8>, Error> {
-        let mut len_buf = [0u8; 2];
-        self.read_exact(&mut len_buf)?;
-        let len = u16::from_be_bytes(len_buf) as usize;
-        let mut buf: Vec<u8> = vec![
-            0u8;
-            len + super::FRAME_PREFIX_SIZE
-                + super::FRAME_SUFFIX_SIZE
-        ];
-        buf[0..2].copy_from_slice(&len_buf);
-        self.read_exact(&mut buf[2..])?;
+        let mut buf = vec![];
+        self.recv_frame_into(&mut buf)?;
         Ok(buf)
     }
 
+    fn recv_frame_into(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let prefix = read_prefix(self)?;
+        let len = u16::from_be_bytes([prefix[0], prefix[1]]) as usize;
+        // Does not re-allocate if the buffer has enough capacity
+        buf.resize(len + FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE, 0u8);
+        buf[..FRAME_PREFIX_SIZE].copy_from_slice(&prefix);
+        self.read_exact(&mut buf[FRAME_PREFIX_SIZE..])?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            remote = ?self.peer_addr().ok(),
+            size = buf.len(),
+            "frame received"
+        );
+        Ok(buf.len())
+    }
+
     fn recv_raw(&mut self, len: usize) -> Result<Vec<u8>, Error> {
         let mut buf: Vec<u8> = vec![0u8; len];
         self.read_exact(&mut buf)?;
@@ -166,9 +776,21 @@ impl SendFrame for TcpStream {
     fn send_frame(&mut self, data: &[u8]) -> Result<usize, Error> {
         let len = data.len();
         if len > super::MAX_FRAME_SIZE {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                remote = ?self.peer_addr().ok(),
+                size = len,
+                "oversized frame rejected"
+            );
             return Err(Error::OversizedFrame(len));
         }
         self.write_all(data)?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            remote = ?self.peer_addr().ok(),
+            size = len,
+            "frame sent"
+        );
         Ok(len)
     }
 
@@ -176,4 +798,397 @@ impl SendFrame for TcpStream {
         self.write_all(data)?;
         Ok(data.len())
     }
+
+    fn send_frame_vectored(&mut self, parts: &[&[u8]]) -> Result<usize, Error> {
+        let len = parts.iter().map(|part| part.len()).sum::<usize>();
+        if len > super::MAX_FRAME_SIZE {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                remote = ?self.peer_addr().ok(),
+                size = len,
+                "oversized frame rejected"
+            );
+            return Err(Error::OversizedFrame(len));
+        }
+        // Empty parts are skipped, since otherwise a zero-sized write would
+        // be indistinguishable from the closed socket
+        let mut remaining = parts
+            .iter()
+            .copied()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        let mut first = 0usize;
+        // `Write::write_all_vectored` is not stable yet, and
+        // `IoSlice::advance_slices` is not available on our MSRV
+        while first < remaining.len() {
+            let slices = remaining[first..]
+                .iter()
+                .map(|part| IoSlice::new(part))
+                .collect::<Vec<_>>();
+            let mut written = match self.write_vectored(&slices) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => written,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                    continue
+                }
+                Err(err) => return Err(err.into()),
+            };
+            // Skipping fully written parts and trimming the partially
+            // written one
+            while written > 0 {
+                let part = remaining[first];
+                if written < part.len() {
+                    remaining[first] = &part[written..];
+                    break;
+                }
+                written -= part.len();
+                first += 1;
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            remote = ?self.peer_addr().ok(),
+            size = len,
+            "frame sent"
+        );
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// In-memory duplex which receives all the frames sent to it
+    #[derive(Clone, Default)]
+    struct MemDuplex(Arc<Mutex<VecDeque<Vec<u8>>>>);
+
+    impl RecvFrame for MemDuplex {
+        fn recv_frame(&mut self) -> Result<Vec<u8>, Error> {
+            self.0
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or(Error::SocketIo(io::ErrorKind::UnexpectedEof))
+        }
+
+        fn recv_raw(&mut self, _len: usize) -> Result<Vec<u8>, Error> {
+            self.recv_frame()
+        }
+    }
+
+    impl SendFrame for MemDuplex {
+        fn send_frame(&mut self, frame: &[u8]) -> Result<usize, Error> {
+            self.0.lock().unwrap().push_back(frame.to_vec());
+            Ok(frame.len())
+        }
+
+        fn send_raw(&mut self, raw_frame: &[u8]) -> Result<usize, Error> {
+            self.send_frame(raw_frame)
+        }
+    }
+
+    impl Duplex for MemDuplex {
+        fn as_receiver(&mut self) -> &mut dyn RecvFrame { self }
+
+        fn as_sender(&mut self) -> &mut dyn SendFrame { self }
+
+        fn split(
+            self,
+        ) -> (Box<dyn RecvFrame + Send>, Box<dyn SendFrame + Send>) {
+            (Box::new(self.clone()), Box::new(self))
+        }
+    }
+
+    #[test]
+    fn test_duplex_io() {
+        let mut io = DuplexIo::with(MemDuplex::default());
+
+        io.write_all(b"Hello").unwrap();
+        io.write_all(b"").unwrap();
+        io.write_all(b" world").unwrap();
+        io.flush().unwrap();
+
+        let mut buf = [0u8; 3];
+        io.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hel");
+        let mut buf = [0u8; 8];
+        io.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"lo world");
+
+        let data = vec![0xA5u8; MAX_FRAME_PAYLOAD_SIZE + 10];
+        io.write_all(&data).unwrap();
+        assert_eq!(io.duplex.0.lock().unwrap().len(), 2);
+        let mut buf = vec![0u8; data.len()];
+        io.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data);
+
+        assert_eq!(
+            io.read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_message_io() {
+        let max = MAX_FRAME_PAYLOAD_SIZE * 3;
+        let mut io = MessageIo::with(MemDuplex::default(), max);
+
+        io.send_message(b"").unwrap();
+        assert_eq!(io.recv_message().unwrap(), b"");
+
+        io.send_message(b"Hello world").unwrap();
+        assert_eq!(io.duplex.0.lock().unwrap().len(), 1);
+        assert_eq!(io.recv_message().unwrap(), b"Hello world");
+
+        let payload = (0..MAX_FRAME_PAYLOAD_SIZE * 2 + 10)
+            .map(|no| no as u8)
+            .collect::<Vec<_>>();
+        io.send_message(&payload).unwrap();
+        assert_eq!(io.duplex.0.lock().unwrap().len(), 3);
+        assert_eq!(io.recv_message().unwrap(), payload);
+
+        io.send_message(&[0xA5u8; MAX_FRAME_PAYLOAD_SIZE * 3 + 1])
+            .unwrap();
+        assert_eq!(
+            io.recv_message(),
+            Err(Error::OversizedMessage {
+                size: max + 1,
+                max
+            })
+        );
+
+        let mut io = MessageIo::with(MemDuplex::default(), max);
+        io.duplex
+            .send_frame(&PlainTranscoder.encrypt(&[MESSAGE_LAST][..]))
+            .unwrap();
+        assert_eq!(
+            io.recv_message(),
+            Err(Error::FrameBroken("message frames are out of order"))
+        );
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        let mut duplex = MemDuplex::default();
+        for _ in 0..3 {
+            duplex
+                .send_frame(&PlainTranscoder.encrypt(&b"Some message"[..]))
+                .unwrap();
+        }
+        let frame_len = 12 + FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE;
+
+        let budget = MemoryBudget::with_limit(100);
+        let mut receiver = BudgetedRecv::with(duplex.clone(), budget.clone());
+        receiver.recv_frame().unwrap();
+        receiver.recv_frame().unwrap();
+        assert_eq!(budget.used(), 2 * frame_len);
+        assert_eq!(
+            receiver.recv_frame(),
+            Err(Error::MemoryLimit {
+                requested: frame_len,
+                available: 100 - 2 * frame_len
+            })
+        );
+        // The frame is kept and no new frames are read until memory is
+        // released
+        duplex.send_frame(&[]).unwrap();
+        assert!(matches!(
+            receiver.recv_frame(),
+            Err(Error::MemoryLimit { .. })
+        ));
+        assert_eq!(duplex.0.lock().unwrap().len(), 1);
+
+        budget.release(frame_len);
+        let frame = receiver.recv_frame().unwrap();
+        assert_eq!(PlainTranscoder.decrypt(frame).unwrap(), b"Some message");
+        assert_eq!(budget.used(), 2 * frame_len);
+
+        // Budget is shared with other receivers
+        let mut other =
+            BudgetedRecv::with(MemDuplex::default(), budget.clone());
+        other.receiver.send_frame(&[0u8; 40]).unwrap();
+        assert!(matches!(other.recv_frame(), Err(Error::MemoryLimit { .. })));
+        budget.release(2 * frame_len);
+        assert_eq!(budget.used(), 0);
+        assert_eq!(other.recv_frame().unwrap().len(), 40);
+        assert_eq!(budget.available(), 60);
+    }
+
+    /// In-memory receiver blocking until some frame is sent to it
+    struct ChannelRecv(mpsc::Receiver<Vec<u8>>);
+
+    impl RecvFrame for ChannelRecv {
+        fn recv_frame(&mut self) -> Result<Vec<u8>, Error> {
+            self.0
+                .recv()
+                .map_err(|_| Error::SocketIo(io::ErrorKind::UnexpectedEof))
+        }
+
+        fn recv_raw(&mut self, _len: usize) -> Result<Vec<u8>, Error> {
+            self.recv_frame()
+        }
+    }
+
+    #[test]
+    fn test_timeout_recv() {
+        let (tx, rx) = mpsc::channel();
+        let mut receiver =
+            TimeoutRecv::with(ChannelRecv(rx), Duration::from_millis(50));
+
+        let start = std::time::Instant::now();
+        assert_eq!(receiver.recv_frame(), Err(Error::TimedOut));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(receiver.is_pending());
+        assert_eq!(receiver.recv_raw(10), Err(Error::TimedOut));
+
+        // Frame received by the timed out operation is not lost
+        tx.send(b"frame".to_vec()).unwrap();
+        receiver.set_timeout(Duration::from_secs(5));
+        assert_eq!(receiver.recv_frame().unwrap(), b"frame");
+        assert!(!receiver.is_pending());
+
+        tx.send(b"another".to_vec()).unwrap();
+        assert_eq!(receiver.recv_raw(7).unwrap(), b"another");
+        assert!(receiver.into_inner().is_some());
+    }
+
+    #[test]
+    fn test_incomplete_prefix() {
+        let mut reader = io::Cursor::new(vec![0u8, 12, 0, 0, 0]);
+        assert_eq!(
+            read_prefix(&mut reader),
+            Err(Error::FrameBroken("incomplete prefix"))
+        );
+
+        // Stream closed at the frame boundary
+        assert_eq!(
+            read_prefix(&mut io::Cursor::new(vec![])),
+            Err(Error::SocketIo(io::ErrorKind::UnexpectedEof))
+        );
+
+        let frame = PlainTranscoder.encrypt(&b"Some message"[..]);
+        let prefix = read_prefix(&mut io::Cursor::new(&frame)).unwrap();
+        assert_eq!(prefix[..], frame[..FRAME_PREFIX_SIZE]);
+    }
+
+    #[test]
+    fn test_frame_reader() {
+        let messages: [&[u8]; 3] = [b"first", b"", b"the third message"];
+        let data = messages
+            .iter()
+            .flat_map(|msg| PlainTranscoder.encrypt(*msg))
+            .collect::<Vec<u8>>();
+        let len = data.len();
+
+        let mut reader = FrameReader::with(io::Cursor::new(data));
+        let mut capacity = 0;
+        for msg in &messages {
+            let frame = reader.recv_frame_ref().unwrap();
+            assert_eq!(PlainTranscoder.decrypt(frame).unwrap(), *msg);
+            capacity = capacity.max(reader.buf.capacity());
+        }
+        assert_eq!(capacity, reader.buf.capacity());
+        assert_eq!(
+            reader.recv_frame_ref(),
+            Err(Error::SocketIo(io::ErrorKind::UnexpectedEof))
+        );
+        assert_eq!(reader.into_inner().position() as usize, len);
+    }
+
+    #[test]
+    fn test_frame_reader_skip() {
+        let long = vec![0xA5u8; 5000];
+        let data = [&b"first"[..], &long, b"last"]
+            .iter()
+            .flat_map(|msg| PlainTranscoder.encrypt(*msg))
+            .collect::<Vec<u8>>();
+
+        let mut reader = FrameReader::with(io::Cursor::new(data));
+        assert_eq!(reader.skip_frame(), Ok(5));
+        assert_eq!(reader.skip_frame(), Ok(long.len()));
+        assert!(reader.buf.is_empty());
+        let frame = reader.recv_frame_ref().unwrap();
+        assert_eq!(PlainTranscoder.decrypt(frame).unwrap(), b"last");
+        assert_eq!(
+            reader.skip_frame(),
+            Err(Error::SocketIo(io::ErrorKind::UnexpectedEof))
+        );
+    }
+
+    #[test]
+    fn test_tcp_incomplete_prefix() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let sender = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(&[0u8, 12, 0, 0, 0]).unwrap();
+        });
+        let (mut stream, _) = listener.accept().unwrap();
+        sender.join().unwrap();
+        assert_eq!(
+            stream.recv_frame(),
+            Err(Error::FrameBroken("incomplete prefix"))
+        );
+    }
+
+    #[test]
+    fn test_nonblocking_connect() {
+        let timeout = Duration::from_secs(5);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let dest = listener.local_addr().unwrap();
+
+        let stream =
+            TcpStream::connect_inet_socket_timeout(dest.into(), timeout)
+                .unwrap();
+        let (mut peer, peer_addr) = listener.accept().unwrap();
+        assert_eq!(stream.local_addr().unwrap(), peer_addr);
+        assert_eq!(stream.peer_addr().unwrap(), dest);
+
+        // Connected socket must be returned in blocking mode, so the read
+        // waits for the data instead of failing
+        stream.set_read_timeout(None).unwrap();
+        let (tx, rx) = mpsc::channel();
+        let reader = thread::spawn(move || {
+            tx.send((&stream).read(&mut [0u8; 1]).map_err(|err| err.kind()))
+                .unwrap()
+        });
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Empty));
+        peer.write_all(&[1]).unwrap();
+        reader.join().unwrap();
+        assert_eq!(rx.recv().unwrap(), Ok(1));
+
+        drop(listener);
+        assert_eq!(
+            TcpStream::connect_inet_socket_timeout(dest.into(), timeout)
+                .unwrap_err(),
+            Error::ConnectionRefused
+        );
+    }
+
+    #[test]
+    fn test_connect_bind_source() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let dest = InetSocketAddr::from(listener.local_addr().unwrap());
+        let source = InetAddr::from([127, 0, 0, 1]);
+
+        let stream =
+            TcpStream::connect_inet_socket_from(dest, Some(source)).unwrap();
+        let (_, peer_addr) = listener.accept().unwrap();
+        assert_eq!(InetAddr::from(peer_addr.ip()), source);
+        assert_eq!(stream.local_addr().unwrap(), peer_addr);
+
+        assert_eq!(
+            TcpStream::connect_inet_socket_from(
+                dest,
+                Some(InetAddr::from(std::net::Ipv6Addr::LOCALHOST))
+            )
+            .unwrap_err(),
+            Error::SourceFamilyMismatch
+        );
+    }
 }
@@ -24,13 +24,19 @@ pub mod brontide;
 pub mod ftcp;
 pub mod generic;
 pub mod socket_addr;
+pub mod socks5;
 pub mod websocket;
 #[cfg(feature = "zmq")]
 pub mod zmqsocket;
 
 use std::io::ErrorKind;
 
-pub use socket_addr::{FramingProtocol, LocalSocketAddr, RemoteSocketAddr};
+use inet2_addr::AddrParseError;
+
+pub use socket_addr::{
+    FramingProtocol, IntoRemoteSocketAddr, LocalSocketAddr, RemoteSocketAddr,
+    RemoteSocketAddrWith,
+};
 #[cfg(feature = "zmq")]
 pub use zmqsocket::{ZmqSocketAddr, ZmqType, ZMQ_CONTEXT};
 
@@ -50,15 +56,73 @@ pub const FRAME_SUFFIX_SIZE: usize = 16;
 /// Maximum size of the frame payload which may be expressed by two bytes
 pub const MAX_FRAME_PAYLOAD_SIZE: usize = 0xFFFF;
 
+/// Computes number of frames required to transfer a payload of `payload_len`
+/// bytes, when each of the frames is bounded by [`MAX_FRAME_PAYLOAD_SIZE`].
+/// Zero-length payload does not require any frames.
+#[inline]
+pub fn frame_count(payload_len: usize) -> usize {
+    (payload_len + MAX_FRAME_PAYLOAD_SIZE - 1) / MAX_FRAME_PAYLOAD_SIZE
+}
+
+/// Splits payload into frame-sized slices, each of which is bounded by
+/// [`MAX_FRAME_PAYLOAD_SIZE`]. The number of produced slices is equal to the
+/// value returned by [`frame_count`].
+#[inline]
+pub fn chunk_payload(payload: &[u8]) -> impl Iterator<Item = &[u8]> {
+    payload.chunks(MAX_FRAME_PAYLOAD_SIZE)
+}
+
+/// Constructs a correctly framed unencrypted frame with zero-length payload,
+/// which may be used as a no-op message for keepalives
+#[inline]
+pub fn empty_frame() -> Vec<u8> {
+    vec![0u8; FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE]
+}
+
+/// Validates frame structure, checking that its size is within the frame size
+/// limits and that the payload length declared in the frame prefix matches the
+/// actual size of the frame payload. Returns the frame payload.
+///
+/// # Errors
+/// * [`Error::OversizedFrame`] if the frame exceeds [`MAX_FRAME_SIZE`]
+/// * [`Error::FrameTooSmall`] if the frame can't contain prefix and suffix
+/// * [`Error::InvalidLength`] if the declared payload length is wrong
+pub fn validate_frame(frame: &[u8]) -> Result<&[u8], Error> {
+    let len = frame.len();
+    if len > MAX_FRAME_SIZE {
+        return Err(Error::OversizedFrame(len));
+    }
+    if len < FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE {
+        return Err(Error::FrameTooSmall(len));
+    }
+    let declared = u16::from_be_bytes([frame[0], frame[1]]);
+    let expected = (len - FRAME_PREFIX_SIZE - FRAME_SUFFIX_SIZE) as u16;
+    if declared != expected {
+        return Err(Error::InvalidLength {
+            expected,
+            actual: declared,
+        });
+    }
+    Ok(&frame[FRAME_PREFIX_SIZE..len - FRAME_SUFFIX_SIZE])
+}
+
 /// Transport protocol-level errors
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Display, Error, From)]
 #[display(doc_comments)]
 pub enum Error {
     /// I/O socket error, generated by underlying socket implementation
     /// (POSIX or TCP). Error type is {_0:?}
-    #[from]
     SocketIo(std::io::ErrorKind),
 
+    /// connection was refused by the remote peer, which is probably offline
+    ConnectionRefused,
+
+    /// connection was reset by the remote peer
+    ConnectionReset,
+
+    /// connection was closed by the remote peer with reason {_0:02x?}
+    ClosedByPeer(Vec<u8>),
+
     /// ZMQ socket error, type {0}
     #[cfg(feature = "zmq")]
     Zmq(zmqsocket::Error),
@@ -71,6 +135,10 @@ pub enum Error {
     /// ZMQ socket
     RequiresLocalSocket,
 
+    /// the socket does not support multipeer connectivity, so routed frames
+    /// can't be sent or received with it
+    MultipeerUnsupported,
+
     /// the provided frame size ({0}) exceeds frame size limit of
     /// MAX_FRAME_SIZE bytes
     OversizedFrame(usize),
@@ -84,12 +152,27 @@ pub enum Error {
     /// read length {actual} is not equal to the expected length {expected}
     InvalidLength { expected: u16, actual: u16 },
 
+    /// frame of {requested} bytes exceeds remaining memory budget of
+    /// {available} bytes
+    MemoryLimit { requested: usize, available: usize },
+
+    /// message of {size} bytes exceeds maximum allowed message size of {max}
+    /// bytes
+    OversizedMessage { size: usize, max: usize },
+
     /// message does not contain Brontide length header
     NoBrontideHeader,
 
+    /// connections over {0} framing protocol are not supported
+    UnsupportedFramingProtocol(FramingProtocol),
+
     /// connections over Tor protocol are not yet supported
     TorNotSupportedYet,
 
+    /// source address family does not match the family of the destination
+    /// address
+    SourceFamilyMismatch,
+
     /// read or write attempt exceeded socket timeout
     TimedOut,
 
@@ -97,19 +180,186 @@ pub enum Error {
     #[from]
     Handshake(HandshakeError),
 
+    /// SOCKS5 proxy error: {0}
+    #[from]
+    Socks5(socks5::Error),
+
+    /// invalid socket address: {0}
+    #[from]
+    AddrParse(AddrParseError),
+
+    /// handshake with the remote peer was rejected: {0}
+    HandshakeFailed(String),
+
     /// use of {0} API requires compilatino with `keygen` feature enabled
     KeygenFeatureRequired(&'static str),
 }
 
-impl From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Error {
-        match err.kind() {
+impl Error {
+    /// Returns human-readable explanation of the error with a suggestion on
+    /// how it can be fixed, suitable for presenting to end users (for
+    /// instance, in command-line tools)
+    pub fn explain(&self) -> String {
+        match self {
+            Error::SocketIo(kind) => format!(
+                "the operating system reported an I/O error ({:?}) on the \
+                 network socket; check network connectivity and that the \
+                 address is reachable from this machine",
+                kind
+            ),
+            Error::ConnectionRefused => s!(
+                "the remote peer refused the connection; make sure the peer \
+                 is online and listening on the specified address and port"
+            ),
+            Error::ConnectionReset => s!(
+                "the remote peer has closed the connection unexpectedly; it \
+                 may have restarted or rejected the session, so try to \
+                 reconnect later"
+            ),
+            Error::ClosedByPeer(reason) => format!(
+                "the remote peer has closed the connection providing reason \
+                 \"{}\"; check the reason and fix the problem before \
+                 reconnecting",
+                String::from_utf8_lossy(reason)
+            ),
+            #[cfg(feature = "zmq")]
+            Error::Zmq(err) => format!(
+                "ZMQ socket operation failed ({}); check that the socket \
+                 address is correct and the service is running",
+                err
+            ),
+            Error::ServiceOffline => s!(
+                "the service is not responding; check that it is running and \
+                 reachable, then retry"
+            ),
+            Error::RequiresLocalSocket => s!(
+                "this operation works only with peers on the same machine; \
+                 use a POSIX, IPC or in-process ZMQ socket address instead of \
+                 a network one"
+            ),
+            Error::MultipeerUnsupported => s!(
+                "this socket is connected to a single peer and can't route \
+                 frames; use a ZMQ socket for multipeer connectivity or send \
+                 frames without routing information"
+            ),
+            Error::OversizedFrame(len) => format!(
+                "a frame of {} bytes exceeds the protocol limit of {} bytes; \
+                 split the data into smaller messages",
+                len, MAX_FRAME_SIZE
+            ),
+            Error::FrameTooSmall(len) => format!(
+                "a frame of {} bytes is too small to contain frame header \
+                 and MAC; the remote peer likely uses an incompatible \
+                 protocol",
+                len
+            ),
+            Error::FrameBroken(details) => format!(
+                "received data do not form a valid frame ({}); the remote \
+                 peer likely uses an incompatible protocol or the connection \
+                 is corrupted",
+                details
+            ),
+            Error::InvalidLength { expected, actual } => format!(
+                "frame length {} does not match the expected length {}; the \
+                 connection data are corrupted, so reconnect to the peer",
+                actual, expected
+            ),
+            Error::MemoryLimit {
+                requested,
+                available,
+            } => format!(
+                "a frame of {} bytes can't be received since only {} bytes \
+                 of the memory budget are left; process and release pending \
+                 frames or increase the budget limit",
+                requested, available
+            ),
+            Error::OversizedMessage { size, max } => format!(
+                "a message of {} bytes exceeds the limit of {} bytes; split \
+                 the data into smaller messages or increase the limit",
+                size, max
+            ),
+            Error::NoBrontideHeader => s!(
+                "the message lacks Brontide length header; the remote peer \
+                 likely does not speak the Brontide protocol"
+            ),
+            Error::UnsupportedFramingProtocol(proto) => format!(
+                "connections over {} are not implemented yet; use an FTCP \
+                 (`lnp://`) address instead",
+                proto
+            ),
+            Error::TorNotSupportedYet => s!(
+                "this operation does not support onion addresses; connect \
+                 to onion addresses through Tor SOCKS5 proxy or use an IPv4 \
+                 or IPv6 address of the peer instead"
+            ),
+            Error::SourceFamilyMismatch => s!(
+                "the source address to bind to and the destination address \
+                 belong to different families; use an IPv4 source for IPv4 \
+                 destinations and an IPv6 source for IPv6 ones"
+            ),
+            Error::TimedOut => s!(
+                "the operation did not complete in time; the remote peer may \
+                 be overloaded or the network slow, so retry or increase the \
+                 socket timeout"
+            ),
+            Error::Handshake(err) => format!(
+                "the encrypted session handshake failed ({}); check that the \
+                 remote node id is correct",
+                err
+            ),
+            Error::Socks5(err) => format!(
+                "the SOCKS5 proxy was not able to connect ({}); check that \
+                 the proxy (like Tor daemon) is running and the destination \
+                 is reachable through it",
+                err
+            ),
+            Error::AddrParse(err) => format!(
+                "the address of the remote peer can't be parsed ({}); {}",
+                err,
+                err.explain()
+            ),
+            Error::HandshakeFailed(reason) => format!(
+                "the remote peer rejected the handshake ({}); check that \
+                 both peers use compatible protocol versions and keys",
+                reason
+            ),
+            Error::KeygenFeatureRequired(api) => format!(
+                "{} requires key generation support; rebuild with the \
+                 `keygen` feature",
+                api
+            ),
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        match kind {
             ErrorKind::WouldBlock | ErrorKind::TimedOut => Error::TimedOut,
+            ErrorKind::ConnectionRefused => Error::ConnectionRefused,
+            ErrorKind::ConnectionReset => Error::ConnectionReset,
             kind => Error::SocketIo(kind),
         }
     }
 }
 
+impl From<std::io::Error> for Error {
+    #[inline]
+    fn from(err: std::io::Error) -> Error { err.kind().into() }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::SocketIo(kind) => kind.into(),
+            Error::ConnectionRefused => ErrorKind::ConnectionRefused.into(),
+            Error::ConnectionReset => ErrorKind::ConnectionReset.into(),
+            Error::TimedOut => ErrorKind::TimedOut.into(),
+            err => std::io::Error::new(ErrorKind::Other, err),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct RoutedFrame {
     /// Previous hop where we received message from during the routing
@@ -122,6 +372,42 @@ pub struct RoutedFrame {
     pub msg: Vec<u8>,
 }
 
+impl RoutedFrame {
+    /// Peels a single routing layer from the frame message for source-routed
+    /// (onion-style) forwarding. The message is interpreted as a 1-byte
+    /// length of the next hop id, followed by the next hop id and the rest of
+    /// the message, which forms the message of the inner frame.
+    ///
+    /// # Returns
+    /// Tuple of the next hop id and the inner frame. The inner frame keeps
+    /// the originator of the current frame, has the destination of the
+    /// current frame as its previous hop and the next hop as its destination.
+    ///
+    /// # Errors
+    /// [`Error::FrameBroken`] if the message does not contain a valid routing
+    /// layer
+    pub fn peel_layer(&self) -> Result<(Vec<u8>, RoutedFrame), Error> {
+        let (len, data) = self
+            .msg
+            .split_first()
+            .ok_or(Error::FrameBroken("no routing layer in routed frame"))?;
+        let len = *len as usize;
+        if data.len() < len {
+            return Err(Error::FrameBroken(
+                "next hop id exceeds routing layer length",
+            ));
+        }
+        let (next_hop, rest) = data.split_at(len);
+        let inner = RoutedFrame {
+            hop: self.dst.clone(),
+            src: self.src.clone(),
+            dst: next_hop.to_vec(),
+            msg: rest.to_vec(),
+        };
+        Ok((next_hop.to_vec(), inner))
+    }
+}
+
 /// Marker trait for types that can provide a concrete implementation for both
 /// frame parser implementing [`RecvFrame`] and frame composer implementing
 /// [`SendFrame`]. These types must also implement [`amplify::Bipolar`], i.e.
@@ -130,6 +416,13 @@ pub trait Duplex {
     fn as_receiver(&mut self) -> &mut dyn RecvFrame;
     fn as_sender(&mut self) -> &mut dyn SendFrame;
     fn split(self) -> (Box<dyn RecvFrame + Send>, Box<dyn SendFrame + Send>);
+
+    /// Returns maximum size of the frame which can be sent over the
+    /// transport. Generic code must use this value instead of
+    /// [`MAX_FRAME_SIZE`] constant, since some transports (like the ones
+    /// using datagrams bounded by the network MTU) have stricter limits.
+    #[inline]
+    fn max_frame_size(&self) -> usize { MAX_FRAME_SIZE }
 }
 
 /// Frame receiving type which is able to parse raw data (streamed or framed by
@@ -153,6 +446,28 @@ pub trait RecvFrame {
     /// I/O error type
     fn recv_raw(&mut self, len: usize) -> Result<Vec<u8>, Error>;
 
+    /// Receives a single frame like [`RecvFrame::recv_frame`], but puts it
+    /// into the caller-provided buffer, replacing its content. Reusing the
+    /// buffer across the calls avoids allocation for each of the frames.
+    ///
+    /// Default implementation copies the frame returned by
+    /// [`RecvFrame::recv_frame`]; transports reading from a byte stream
+    /// override it to read directly into the buffer.
+    ///
+    /// # Returns
+    /// Number of bytes written into the buffer, which is equal to the size of
+    /// the frame and the new length of the buffer
+    ///
+    /// # Errors
+    /// Same as for [`RecvFrame::recv_frame`]; in case of an error the buffer
+    /// content is unspecified
+    fn recv_frame_into(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let frame = self.recv_frame()?;
+        buf.clear();
+        buf.extend_from_slice(&frame);
+        Ok(buf.len())
+    }
+
     /// Receive frame like with [`RecvFrame::recv_frame`], but only originating
     /// from the specified remote address.
     ///
@@ -163,19 +478,15 @@ pub trait RecvFrame {
     ///   frame). The id is specific for the underlying overlaid protocol.
     ///
     /// # Errors
-    /// Returns only [`Error::SocketIo`] if the overlaid protocol errors with
-    /// I/O error type
-    ///
-    /// # Panics
-    /// Default implementation panics, since most of the framing protocols do
-    /// not support multipeer sockets and [`RecvFrame::recv_frame`] must be
-    /// used instead (currently only ZMQ-based connections support this
-    /// operation)
+    /// * [`Error::SocketIo`] if the overlaid protocol errors with I/O error
+    ///   type
+    /// * [`Error::MultipeerUnsupported`] if the socket does not support
+    ///   multipeer connectivity. This is returned by the default
+    ///   implementation, since most of the framing protocols do not support
+    ///   multipeer sockets and [`RecvFrame::recv_frame`] must be used instead
+    ///   (currently only ZMQ-based connections support this operation)
     fn recv_routed(&mut self) -> Result<RoutedFrame, Error> {
-        // We panic here because this is a program architecture design
-        // error and developer must be notified about it; the program using
-        // this pattern can't work
-        panic!("Multipeer sockets are not possible with the chosen transport")
+        Err(Error::MultipeerUnsupported)
     }
 }
 
@@ -218,6 +529,25 @@ pub trait SendFrame {
     ///   type
     fn send_raw(&mut self, raw_frame: &[u8]) -> Result<usize, Error>;
 
+    /// Sends a single frame composed of several parts (like frame prefix,
+    /// payload and suffix held in separate buffers) as if they were
+    /// concatenated into a single frame passed to [`SendFrame::send_frame`].
+    ///
+    /// Default implementation concatenates the parts; transports writing to
+    /// a byte stream override it to send all the parts with a single
+    /// vectored write, avoiding the copy.
+    ///
+    /// # Returns
+    /// In case of success, number of bytes send, which is equal to the total
+    /// size of the parts
+    ///
+    /// # Errors
+    /// Same as for [`SendFrame::send_frame`], with the frame length limit
+    /// applied to the total size of the parts
+    fn send_frame_vectored(&mut self, parts: &[&[u8]]) -> Result<usize, Error> {
+        self.send_frame(&parts.concat())
+    }
+
     /// Sends a single frame of data structured as a byte string to a specific
     /// receiver with `remote_id`. Function works like [`RecvFrame::recv_frame`]
     /// and is used for the underlying protocols supporting multipeer
@@ -235,12 +565,11 @@ pub trait SendFrame {
     ///   type
     /// * [`Error::OversizedFrame`] if the provided data length exceeds
     ///   [`MAX_FRAME_SIZE`]
-    ///
-    /// # Panics
-    /// Default implementation panics, since the most of framing protocols do
-    /// not support multipeer sockets and [`SendFrame::send_frame`] must be
-    /// used instead (currently only ZMQ-based connections support this
-    /// operation)
+    /// * [`Error::MultipeerUnsupported`] if the socket does not support
+    ///   multipeer connectivity. This is returned by the default
+    ///   implementation, since most of the framing protocols do not support
+    ///   multipeer sockets and [`SendFrame::send_frame`] must be used instead
+    ///   (currently only ZMQ-based connections support this operation)
     #[allow(dead_code)]
     fn send_routed(
         &mut self,
@@ -249,10 +578,7 @@ pub trait SendFrame {
         _address: &[u8],
         _data: &[u8],
     ) -> Result<usize, Error> {
-        // We panic here because this is a program architecture design
-        // error and developer must be notified about it; the program using
-        // this pattern can't work
-        panic!("Multipeer sockets are not possible with the chosen transport")
+        Err(Error::MultipeerUnsupported)
     }
 }
 
@@ -271,10 +597,7 @@ pub trait AsyncRecvFrame {
     /// Async version of [`RecvFrame::recv_from`]; pls refer to it for the
     /// function documentation
     async fn async_recv_from(&mut self) -> Result<(Vec<u8>, Vec<u8>), Error> {
-        // We panic here because this is a program architecture design
-        // error and developer must be notified about it; the program using
-        // this pattern can't work
-        panic!("Multipeer sockets are not possible with the chosen transport")
+        Err(Error::MultipeerUnsupported)
     }
 }
 
@@ -297,12 +620,228 @@ pub trait AsyncSendFrame {
     /// function documentation
     async fn async_send_to(
         &mut self,
-        remote_id: &[u8],
-        frame: &[u8],
+        _remote_id: &[u8],
+        _frame: &[u8],
     ) -> Result<usize, Error> {
-        // We panic here because this is a program architecture design
-        // error and developer must be notified about it; the program using
-        // this pattern can't work
-        panic!("Multipeer sockets are not possible with the chosen transport")
+        Err(Error::MultipeerUnsupported)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Mock of datagram-based transport with frames limited by UDP payload
+    /// size for 1500-byte MTU. Clones share the same frame queue.
+    #[derive(Clone, Default)]
+    struct UdpDuplex(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl RecvFrame for UdpDuplex {
+        fn recv_frame(&mut self) -> Result<Vec<u8>, Error> {
+            self.0.lock().unwrap().pop().ok_or(Error::ConnectionReset)
+        }
+
+        fn recv_raw(&mut self, _len: usize) -> Result<Vec<u8>, Error> {
+            self.recv_frame()
+        }
+    }
+
+    impl SendFrame for UdpDuplex {
+        fn send_frame(&mut self, frame: &[u8]) -> Result<usize, Error> {
+            if frame.len() > self.max_frame_size() {
+                return Err(Error::OversizedFrame(frame.len()));
+            }
+            self.0.lock().unwrap().push(frame.to_vec());
+            Ok(frame.len())
+        }
+
+        fn send_raw(&mut self, raw_frame: &[u8]) -> Result<usize, Error> {
+            self.send_frame(raw_frame)
+        }
+    }
+
+    impl Duplex for UdpDuplex {
+        fn as_receiver(&mut self) -> &mut dyn RecvFrame { self }
+
+        fn as_sender(&mut self) -> &mut dyn SendFrame { self }
+
+        fn split(
+            self,
+        ) -> (Box<dyn RecvFrame + Send>, Box<dyn SendFrame + Send>) {
+            (Box::new(self.clone()), Box::new(self))
+        }
+
+        fn max_frame_size(&self) -> usize { 1472 }
+    }
+
+    #[test]
+    fn test_max_frame_size() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let ftcp = ftcp::Connection::with(
+            std::net::TcpStream::connect(addr).unwrap(),
+            addr.into(),
+        );
+        let mut udp = UdpDuplex::default();
+        assert_eq!(ftcp.max_frame_size(), MAX_FRAME_SIZE);
+        assert_eq!(udp.max_frame_size(), 1472);
+
+        let frame = vec![0u8; 2000];
+        assert_eq!(
+            udp.as_sender().send_frame(&frame),
+            Err(Error::OversizedFrame(2000))
+        );
+        assert_eq!(udp.as_sender().send_frame(&frame[..1472]), Ok(1472));
+
+        let (mut rx, mut tx) = udp.split();
+        assert_eq!(tx.send_frame(&frame[..10]), Ok(10));
+        assert_eq!(rx.recv_frame(), Ok(frame[..10].to_vec()));
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(
+            Error::ConnectionRefused.explain(),
+            "the remote peer refused the connection; make sure the peer is \
+             online and listening on the specified address and port"
+        );
+        assert_eq!(
+            Error::KeygenFeatureRequired("encrypted TCP connect").explain(),
+            "encrypted TCP connect requires key generation support; rebuild \
+             with the `keygen` feature"
+        );
+        assert_eq!(
+            Error::MultipeerUnsupported.explain(),
+            "this socket is connected to a single peer and can't route \
+             frames; use a ZMQ socket for multipeer connectivity or send \
+             frames without routing information"
+        );
+        assert_eq!(
+            Error::OversizedFrame(70000).explain(),
+            "a frame of 70000 bytes exceeds the protocol limit of 65569 \
+             bytes; split the data into smaller messages"
+        );
+    }
+
+    #[test]
+    fn test_io_error_mapping() {
+        let io_err = |kind| Error::from(std::io::Error::from(kind));
+        assert_eq!(
+            io_err(ErrorKind::ConnectionRefused),
+            Error::ConnectionRefused
+        );
+        assert_eq!(io_err(ErrorKind::ConnectionReset), Error::ConnectionReset);
+        assert_eq!(io_err(ErrorKind::TimedOut), Error::TimedOut);
+        assert_eq!(io_err(ErrorKind::WouldBlock), Error::TimedOut);
+        assert_eq!(
+            io_err(ErrorKind::ConnectionAborted),
+            Error::SocketIo(ErrorKind::ConnectionAborted)
+        );
+        assert_eq!(
+            Error::from(ErrorKind::ConnectionRefused),
+            Error::ConnectionRefused
+        );
+
+        for kind in [
+            ErrorKind::ConnectionRefused,
+            ErrorKind::ConnectionReset,
+            ErrorKind::UnexpectedEof,
+        ] {
+            assert_eq!(std::io::Error::from(io_err(kind)).kind(), kind);
+        }
+    }
+
+    #[test]
+    fn test_peel_layer() {
+        let frame = RoutedFrame {
+            hop: b"prev".to_vec(),
+            src: b"alice".to_vec(),
+            dst: b"bob".to_vec(),
+            msg: b"\x05carol\x04dave\x00message".to_vec(),
+        };
+
+        let (next_hop, inner) = frame.peel_layer().unwrap();
+        assert_eq!(next_hop, b"carol");
+        assert_eq!(inner, RoutedFrame {
+            hop: b"bob".to_vec(),
+            src: b"alice".to_vec(),
+            dst: b"carol".to_vec(),
+            msg: b"\x04dave\x00message".to_vec(),
+        });
+
+        let (next_hop, inner) = inner.peel_layer().unwrap();
+        assert_eq!(next_hop, b"dave");
+        assert_eq!(inner, RoutedFrame {
+            hop: b"carol".to_vec(),
+            src: b"alice".to_vec(),
+            dst: b"dave".to_vec(),
+            msg: b"\x00message".to_vec(),
+        });
+
+        // Zero-length next hop id ends the route
+        let (next_hop, inner) = inner.peel_layer().unwrap();
+        assert!(next_hop.is_empty());
+        assert_eq!(inner.msg, b"message");
+
+        let broken = RoutedFrame {
+            msg: b"\x10short".to_vec(),
+            ..inner.clone()
+        };
+        assert!(matches!(broken.peel_layer(), Err(Error::FrameBroken(_))));
+        let empty = RoutedFrame { msg: vec![], ..inner };
+        assert!(matches!(empty.peel_layer(), Err(Error::FrameBroken(_))));
+    }
+
+    #[test]
+    fn test_frame_count() {
+        assert_eq!(frame_count(0), 0);
+        assert_eq!(frame_count(1), 1);
+        assert_eq!(frame_count(MAX_FRAME_PAYLOAD_SIZE), 1);
+        assert_eq!(frame_count(MAX_FRAME_PAYLOAD_SIZE * 3), 3);
+        assert_eq!(frame_count(MAX_FRAME_PAYLOAD_SIZE * 3 + 1), 4);
+    }
+
+    #[test]
+    fn test_empty_frame() {
+        let frame = empty_frame();
+        assert_eq!(validate_frame(&frame), Ok(&[][..]));
+        assert_eq!(frame.len(), FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE);
+
+        let mut frame = vec![0u8; 4 + FRAME_PREFIX_SIZE + FRAME_SUFFIX_SIZE];
+        frame[1] = 4;
+        assert_eq!(validate_frame(&frame), Ok(&[0u8; 4][..]));
+        frame[1] = 5;
+        assert_eq!(
+            validate_frame(&frame),
+            Err(Error::InvalidLength {
+                expected: 4,
+                actual: 5
+            })
+        );
+        assert_eq!(validate_frame(&[0u8; 33]), Err(Error::FrameTooSmall(33)));
+        assert_eq!(
+            validate_frame(&vec![0u8; MAX_FRAME_SIZE + 1]),
+            Err(Error::OversizedFrame(MAX_FRAME_SIZE + 1))
+        );
+    }
+
+    #[test]
+    fn test_chunk_payload() {
+        assert_eq!(chunk_payload(&[]).count(), 0);
+
+        let payload = vec![0xA5u8; MAX_FRAME_PAYLOAD_SIZE * 2];
+        let chunks = chunk_payload(&payload).collect::<Vec<_>>();
+        assert_eq!(chunks.len(), frame_count(payload.len()));
+        assert!(chunks.iter().all(|c| c.len() == MAX_FRAME_PAYLOAD_SIZE));
+
+        let payload = vec![0xA5u8; MAX_FRAME_PAYLOAD_SIZE * 2 + 10];
+        let chunks = chunk_payload(&payload).collect::<Vec<_>>();
+        assert_eq!(chunks.len(), frame_count(payload.len()));
+        assert_eq!(chunks[0].len(), MAX_FRAME_PAYLOAD_SIZE);
+        assert_eq!(chunks[1].len(), MAX_FRAME_PAYLOAD_SIZE);
+        assert_eq!(chunks[2], &[0xA5u8; 10][..]);
+        assert_eq!(chunks.concat(), payload);
     }
 }
@@ -18,23 +18,31 @@
 //! structures like [`crate::NodeAddr`]).
 
 use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 #[cfg(feature = "url")]
 use std::convert::TryFrom;
 #[cfg(any(feature = "url", feature = "zmq"))]
 use std::convert::TryInto;
 use std::net::{IpAddr, SocketAddr};
+use std::ops::Deref;
 use std::str::FromStr;
 
 #[cfg(feature = "url")]
 use inet2_addr::InetAddr;
-use inet2_addr::{InetSocketAddr, NoOnionSupportError};
+use bitcoin_hashes::{sha256, Hash as _, HashEngine};
+use inet2_addr::{
+    InetSocketAddr, InetSocketAddrExt, NoOnionSupportError, Transport,
+};
 #[cfg(all(feature = "serde", feature = "zmq"))]
 use serde_with::{As, DisplayFromStr};
+use strict_encoding::StrictEncode;
 #[cfg(feature = "url")]
-use url::{self, Url};
+use url::{self, Host, Url};
 
 #[cfg(feature = "zmq")]
 use super::zmqsocket;
+use super::{ftcp, Error};
 use crate::{AddrError, UrlString};
 
 #[derive(
@@ -121,14 +129,12 @@ impl FromStr for FramingProtocol {
     Ord,
     Hash,
     Debug,
-    Display,
     StrictEncode,
     StrictDecode
 )]
 pub enum LocalSocketAddr {
     /// Microservices connected using ZeroMQ protocol locally
     #[cfg(feature = "zmq")]
-    #[display("{0}", alt = "lnpz://{0}")]
     Zmq(
         #[cfg_attr(feature = "serde", serde(with = "As::<DisplayFromStr>"))]
         zmqsocket::ZmqSocketAddr,
@@ -136,7 +142,6 @@ pub enum LocalSocketAddr {
 
     /// Local node operating as a separate **process** or **threads** connected
     /// with unencrypted POSIX file I/O (like in c-lightning)
-    #[display("{0}", alt = "lnp:{0}")]
     Posix(String),
 }
 
@@ -146,59 +151,65 @@ pub enum LocalSocketAddr {
     derive(Serialize, Deserialize),
     serde(crate = "serde_crate")
 )]
-#[derive(
-    Clone,
-    Copy,
-    PartialEq,
-    Eq,
-    Hash,
-    Debug,
-    Display,
-    StrictEncode,
-    StrictDecode
-)]
+#[derive(Clone, Copy, Debug, StrictEncode, StrictDecode)]
 #[non_exhaustive]
 pub enum RemoteSocketAddr {
     /// Framed TCP socket connection, that may be served either over plain IP,
     /// IPSec or Tor v3
-    #[display("{0}", alt = "lnp://{0}")]
     Ftcp(InetSocketAddr),
 
     /// Microservices connected using ZeroMQ protocol remotely. Can be used
     /// only with TCP-based ZMQ
     #[cfg(feature = "zmq")]
-    #[display("{0}", alt = "lnpz://{0}")]
     Zmq(SocketAddr),
 
     /// End-to-end encryption over web connection: think of this as LN protocol
     /// streamed over HTTP
-    #[display("{0}", alt = "lnph://{0}")]
     Http(InetSocketAddr),
 
     /// End-to-end encryption over web connection: think of this as LN protocol
     /// streamed over Websocket
     #[cfg(feature = "websocket")]
-    #[display("{0}", alt = "lnpws://{0}")]
     Websocket(InetSocketAddr),
 
     /// SMTP connection: asynchronous end-to-end-over SMTP information transfer
     /// which is useful for ultra-low bandwidth non-real-time connections like
     /// satellite networks
-    #[display("{0}", alt = "lnpm://{0}")]
     Smtp(InetSocketAddr),
 }
 
+// Equivalent remote addresses (i.e. the ones differing only by the use of
+// IPv4-mapped IPv6 form) must be equal and produce the same hash
+impl PartialEq for RemoteSocketAddr {
+    fn eq(&self, other: &Self) -> bool {
+        self.framing_protocol() == other.framing_protocol()
+            && InetSocketAddr::from(self.normalized())
+                == InetSocketAddr::from(other.normalized())
+    }
+}
+
+impl Eq for RemoteSocketAddr {}
+
+impl Hash for RemoteSocketAddr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.framing_protocol().hash(state);
+        InetSocketAddr::from(self.normalized()).hash(state);
+    }
+}
+
 // Fake implementation required to use node addresses with StrictEncode
 // BTreeMaps
 impl PartialOrd for RemoteSocketAddr {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.to_string().partial_cmp(&other.to_string())
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for RemoteSocketAddr {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.to_string().cmp(&other.to_string())
+        self.normalized()
+            .to_string()
+            .cmp(&other.normalized().to_string())
     }
 }
 
@@ -250,6 +261,132 @@ impl RemoteSocketAddr {
             RemoteSocketAddr::Smtp(_) => FramingProtocol::Smtp,
         }
     }
+
+    /// Returns string representation of the socket address (host and port)
+    /// without the URL scheme of the framing protocol, which is used as a
+    /// part of remote node address strings.
+    pub fn to_socket_addr_string(&self) -> String {
+        match self {
+            RemoteSocketAddr::Ftcp(inet) => inet.to_string(),
+            #[cfg(feature = "zmq")]
+            RemoteSocketAddr::Zmq(sa) => sa.to_string(),
+            RemoteSocketAddr::Http(inet) => inet.to_string(),
+            #[cfg(feature = "websocket")]
+            RemoteSocketAddr::Websocket(inet) => inet.to_string(),
+            RemoteSocketAddr::Smtp(inet) => inet.to_string(),
+        }
+    }
+
+    /// Returns remote address with IPv4-mapped IPv6 addresses converted into
+    /// IPv4 form, as defined by [`InetAddr::normalized`].
+    ///
+    /// [`InetAddr::normalized`]: inet2_addr::InetAddr::normalized
+    pub fn normalized(&self) -> Self {
+        match *self {
+            RemoteSocketAddr::Ftcp(inet) => {
+                RemoteSocketAddr::Ftcp(inet.normalized())
+            }
+            #[cfg(feature = "zmq")]
+            RemoteSocketAddr::Zmq(sa) => {
+                // Normalization never turns IP address into an onion one
+                let normalized =
+                    InetSocketAddr::from(sa).normalized().try_into();
+                RemoteSocketAddr::Zmq(normalized.unwrap_or(sa))
+            }
+            RemoteSocketAddr::Http(inet) => {
+                RemoteSocketAddr::Http(inet.normalized())
+            }
+            #[cfg(feature = "websocket")]
+            RemoteSocketAddr::Websocket(inet) => {
+                RemoteSocketAddr::Websocket(inet.normalized())
+            }
+            RemoteSocketAddr::Smtp(inet) => {
+                RemoteSocketAddr::Smtp(inet.normalized())
+            }
+        }
+    }
+
+    /// Computes stable fingerprint of the remote address, which is a SHA256
+    /// hash of the strict-encoded framing protocol, normalized address and
+    /// port. Equivalent remote addresses have the same fingerprint, so it can
+    /// be used as a key for caching connections.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let data = self
+            .normalized()
+            .strict_serialize()
+            .expect("in-memory strict encoding of remote address can't fail");
+        let mut engine = sha256::Hash::engine();
+        engine.input(&data);
+        sha256::Hash::from_engine(engine).into_inner()
+    }
+}
+
+/// Remote socket address tagged with arbitrary application-specific metadata
+/// (like peer alias or connection priority), which allows to keep such
+/// information together with the address without maintaining a separate map.
+///
+/// The wrapper dereferences to the [`RemoteSocketAddr`], so all of its
+/// methods are available directly.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display("{addr}")]
+pub struct RemoteSocketAddrWith<T> {
+    /// Remote socket address
+    pub addr: RemoteSocketAddr,
+
+    /// Application-specific metadata attached to the address
+    pub meta: T,
+}
+
+impl<T> RemoteSocketAddrWith<T> {
+    /// Attaches metadata to the remote socket address
+    #[inline]
+    pub fn with(addr: RemoteSocketAddr, meta: T) -> Self {
+        Self { addr, meta }
+    }
+
+    /// Returns reference to the metadata attached to the address
+    #[inline]
+    pub fn meta(&self) -> &T { &self.meta }
+
+    /// Returns mutable reference to the metadata attached to the address
+    #[inline]
+    pub fn meta_mut(&mut self) -> &mut T { &mut self.meta }
+
+    /// Splits the wrapper into the remote socket address and its metadata
+    #[inline]
+    pub fn into_parts(self) -> (RemoteSocketAddr, T) { (self.addr, self.meta) }
+
+    /// Establishes unencrypted framed connection with the remote peer.
+    ///
+    /// # Errors
+    /// [`Error::UnsupportedFramingProtocol`] for all addresses other than
+    /// [`RemoteSocketAddr::Ftcp`]; otherwise errors returned by
+    /// [`ftcp::Connection::connect`].
+    pub fn connect(&self) -> Result<ftcp::Connection, Error> {
+        match self.addr {
+            RemoteSocketAddr::Ftcp(inet) => ftcp::Connection::connect(inet),
+            ref addr => {
+                Err(Error::UnsupportedFramingProtocol(addr.framing_protocol()))
+            }
+        }
+    }
+}
+
+impl<T> Deref for RemoteSocketAddrWith<T> {
+    type Target = RemoteSocketAddr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target { &self.addr }
+}
+
+impl<T> AsRef<RemoteSocketAddr> for RemoteSocketAddrWith<T> {
+    #[inline]
+    fn as_ref(&self) -> &RemoteSocketAddr { &self.addr }
+}
+
+impl<T> From<RemoteSocketAddrWith<T>> for RemoteSocketAddr {
+    #[inline]
+    fn from(tagged: RemoteSocketAddrWith<T>) -> Self { tagged.addr }
 }
 
 impl From<RemoteSocketAddr> for InetSocketAddr {
@@ -266,6 +403,74 @@ impl From<RemoteSocketAddr> for InetSocketAddr {
     }
 }
 
+/// Conversion of transport-level socket addresses into remote addresses with
+/// the corresponding framing protocol, which can be used for connecting to the
+/// remote peer
+pub trait IntoRemoteSocketAddr {
+    /// Converts socket address into [`RemoteSocketAddr`] using the framing
+    /// protocol working over the address transport protocol.
+    ///
+    /// Both TCP and MTCP are mapped to [`RemoteSocketAddr::Ftcp`]: multipath
+    /// TCP falls back to plain TCP for the peers not supporting it, and the
+    /// connection is established over plain TCP.
+    ///
+    /// # Errors
+    /// [`AddrError::Unsupported`] for other transports (like UDP or QUIC),
+    /// since there is no framing protocol working over them yet.
+    fn into_remote_addr(self) -> Result<RemoteSocketAddr, AddrError>;
+}
+
+impl IntoRemoteSocketAddr for InetSocketAddrExt {
+    fn into_remote_addr(self) -> Result<RemoteSocketAddr, AddrError> {
+        match self.0 {
+            Transport::Tcp | Transport::Mtcp => {
+                Ok(RemoteSocketAddr::Ftcp(self.1))
+            }
+            _ => Err(AddrError::Unsupported("framed connections")),
+        }
+    }
+}
+
+// Both plain and alternative forms produce URL strings, which can be parsed
+// back with `FromStr`
+impl fmt::Display for LocalSocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "zmq")]
+            LocalSocketAddr::Zmq(addr) => write!(f, "{}", addr),
+            LocalSocketAddr::Posix(path) => write!(f, "lnp:{}", path),
+        }
+    }
+}
+
+impl fmt::Display for RemoteSocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "zmq")]
+            RemoteSocketAddr::Zmq(addr) => {
+                write!(f, "{}{}", self.url_scheme(), addr)
+            }
+            RemoteSocketAddr::Ftcp(inet)
+            | RemoteSocketAddr::Http(inet)
+            | RemoteSocketAddr::Smtp(inet) => write!(
+                f,
+                "{}{}:{}",
+                self.url_scheme(),
+                inet.address.to_uri_host(),
+                inet.port
+            ),
+            #[cfg(feature = "websocket")]
+            RemoteSocketAddr::Websocket(inet) => write!(
+                f,
+                "{}{}:{}",
+                self.url_scheme(),
+                inet.address.to_uri_host(),
+                inet.port
+            ),
+        }
+    }
+}
+
 #[cfg(feature = "url")]
 impl FromStr for LocalSocketAddr {
     type Err = AddrError;
@@ -351,7 +556,7 @@ impl TryFrom<Url> for LocalSocketAddr {
                 LocalSocketAddr::Posix(url.path().to_owned())
             }
             #[cfg(feature = "zmq")]
-            "lnpz" => {
+            "lnpz" | "tcp" | "ipc" | "inproc" => {
                 LocalSocketAddr::Zmq(zmqsocket::ZmqSocketAddr::try_from(url)?)
             }
             "lnph" | "lnpws" | "lnpm" => {
@@ -367,8 +572,14 @@ impl TryFrom<Url> for RemoteSocketAddr {
     type Error = AddrError;
 
     fn try_from(url: Url) -> Result<Self, Self::Error> {
-        let host = url.host_str().ok_or(AddrError::HostRequired)?.to_owned();
-        let inet_addr = host.parse::<InetAddr>()?;
+        let inet_addr = match url.host().ok_or(AddrError::HostRequired)? {
+            Host::Ipv4(ip) => InetAddr::from(ip),
+            Host::Ipv6(ip) => InetAddr::from(ip),
+            Host::Domain(host) => host
+                .strip_suffix(".onion")
+                .unwrap_or(host)
+                .parse::<InetAddr>()?,
+        };
         let port = url.port().ok_or(AddrError::PortRequired)?;
         let inet_socket_addr = InetSocketAddr::new(inet_addr, port);
         Ok(match url.scheme() {
@@ -389,3 +600,190 @@ impl TryFrom<Url> for RemoteSocketAddr {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::hash_map::DefaultHasher;
+    use std::net::{Ipv4Addr, Ipv6Addr, TcpListener};
+
+    use super::*;
+
+    fn hash(addr: &RemoteSocketAddr) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        addr.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_remote_fingerprint() {
+        let ipv4 = Ipv4Addr::new(127, 0, 0, 1);
+        let mapped = RemoteSocketAddr::with_ip_addr(
+            FramingProtocol::FramedRaw,
+            ipv4.to_ipv6_mapped().into(),
+            9735,
+        );
+        let plain = RemoteSocketAddr::with_ip_addr(
+            FramingProtocol::FramedRaw,
+            ipv4.into(),
+            9735,
+        );
+        assert_ne!(format!("{}", mapped), format!("{}", plain));
+        assert_eq!(mapped.normalized().to_string(), plain.to_string());
+        assert_eq!(mapped, plain);
+        assert_eq!(hash(&mapped), hash(&plain));
+        assert_eq!(mapped.cmp(&plain), Ordering::Equal);
+        assert_eq!(mapped.fingerprint(), plain.fingerprint());
+
+        let other_port = RemoteSocketAddr::with_ip_addr(
+            FramingProtocol::FramedRaw,
+            ipv4.into(),
+            9736,
+        );
+        let other_proto = RemoteSocketAddr::with_ip_addr(
+            FramingProtocol::Http,
+            ipv4.into(),
+            9735,
+        );
+        let other_ip = RemoteSocketAddr::with_ip_addr(
+            FramingProtocol::FramedRaw,
+            Ipv6Addr::LOCALHOST.into(),
+            9735,
+        );
+        for other in [other_port, other_proto, other_ip] {
+            assert_ne!(plain, other);
+            assert_ne!(plain.fingerprint(), other.fingerprint());
+        }
+    }
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct Peer {
+        alias: &'static str,
+        priority: u8,
+    }
+
+    #[test]
+    fn test_remote_with_meta() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = RemoteSocketAddr::with_socket_addr(
+            FramingProtocol::FramedRaw,
+            listener.local_addr().unwrap(),
+        );
+        let mut remote = RemoteSocketAddrWith::with(addr, Peer {
+            alias: "alice",
+            priority: 1,
+        });
+        assert_eq!(*remote, addr);
+        assert_eq!(remote.framing_protocol(), FramingProtocol::FramedRaw);
+        assert_eq!(remote.to_string(), addr.to_string());
+
+        remote.connect().unwrap();
+        listener.accept().unwrap();
+        remote.meta_mut().priority = 2;
+        assert_eq!(remote.meta(), &Peer {
+            alias: "alice",
+            priority: 2
+        });
+
+        let http = RemoteSocketAddrWith::with(
+            RemoteSocketAddr::with_socket_addr(
+                FramingProtocol::Http,
+                listener.local_addr().unwrap(),
+            ),
+            (),
+        );
+        assert_eq!(
+            http.connect().unwrap_err(),
+            Error::UnsupportedFramingProtocol(FramingProtocol::Http)
+        );
+
+        let (addr_back, meta) = remote.into_parts();
+        assert_eq!(addr_back, addr);
+        assert_eq!(meta.alias, "alice");
+    }
+
+    #[test]
+    fn test_into_remote_addr() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let local = listener.local_addr().unwrap();
+        let ext = InetSocketAddrExt::from_str(&format!("tcp://{}", local))
+            .unwrap();
+        let remote = ext.into_remote_addr().unwrap();
+        assert_eq!(remote, RemoteSocketAddr::Ftcp(local.into()));
+        assert_eq!(remote.framing_protocol(), FramingProtocol::FramedRaw);
+
+        RemoteSocketAddrWith::with(remote, ()).connect().unwrap();
+        listener.accept().unwrap();
+
+        assert_eq!(
+            InetSocketAddrExt(Transport::Mtcp, local.into())
+                .into_remote_addr(),
+            Ok(remote)
+        );
+        for ext in [
+            InetSocketAddrExt(Transport::Udp, local.into()),
+            InetSocketAddrExt(Transport::Quic, local.into()),
+        ] {
+            assert_eq!(
+                ext.into_remote_addr(),
+                Err(AddrError::Unsupported("framed connections"))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "url")]
+    fn test_display_roundtrip() {
+        fn check_remote(remote: RemoteSocketAddr) {
+            let s = remote.to_string();
+            assert_eq!(RemoteSocketAddr::from_str(&s), Ok(remote), "{}", s);
+            assert_eq!(remote.to_url_string(), s);
+        }
+        fn check_local(local: LocalSocketAddr) {
+            let s = local.to_string();
+            assert_eq!(local.to_url_string(), s);
+            assert_eq!(LocalSocketAddr::from_str(&s), Ok(local), "{}", s);
+        }
+
+        let ipv4 = InetAddr::from(Ipv4Addr::new(127, 0, 0, 1));
+        let ipv6 = InetAddr::from(Ipv6Addr::LOCALHOST);
+        check_remote(RemoteSocketAddr::Ftcp(InetSocketAddr::new(ipv4, 9735)));
+        check_remote(RemoteSocketAddr::Ftcp(InetSocketAddr::new(ipv6, 9735)));
+        check_remote(RemoteSocketAddr::Http(InetSocketAddr::new(ipv6, 80)));
+        check_remote(RemoteSocketAddr::Smtp(InetSocketAddr::new(ipv4, 25)));
+        #[cfg(feature = "websocket")]
+        check_remote(RemoteSocketAddr::Websocket(InetSocketAddr::new(
+            ipv6, 8080,
+        )));
+        #[cfg(feature = "zmq")]
+        check_remote(RemoteSocketAddr::Zmq(SocketAddr::new(
+            Ipv6Addr::LOCALHOST.into(),
+            24,
+        )));
+        #[cfg(feature = "tor")]
+        check_remote(RemoteSocketAddr::Ftcp(InetSocketAddr::new(
+            InetAddr::from_str(
+                "32zzibxmqi2ybxpqyggwwuwz7a3lbvtzoloti7cxoevyvijexvgsfeid",
+            )
+            .unwrap(),
+            9735,
+        )));
+        assert_eq!(
+            RemoteSocketAddr::Ftcp(InetSocketAddr::new(ipv6, 9735))
+                .to_string(),
+            "lnp://[::1]:9735"
+        );
+
+        check_local(LocalSocketAddr::Posix(s!("/tmp/lnp.sock")));
+        #[cfg(feature = "zmq")]
+        {
+            use zmqsocket::ZmqSocketAddr;
+            check_local(LocalSocketAddr::Zmq(ZmqSocketAddr::Inproc(s!("lnp"))));
+            check_local(LocalSocketAddr::Zmq(ZmqSocketAddr::Ipc(s!(
+                "/tmp/lnpz.sock"
+            ))));
+            check_local(LocalSocketAddr::Zmq(ZmqSocketAddr::Tcp(
+                SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 24),
+            )));
+        }
+    }
+}
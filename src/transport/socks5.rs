@@ -0,0 +1,281 @@
+// LNP/BP Core Library implementing LNPBP specifications & standards
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! SOCKS5 protocol messages (RFC 1928) used for connecting to remote peers
+//! through a proxy (like Tor daemon). The module contains only encoding and
+//! parsing of the protocol messages and does not perform any socket I/O, so
+//! it can be tested without a running proxy.
+
+use inet2_addr::InetAddr;
+
+/// SOCKS protocol version
+pub const SOCKS5_VERSION: u8 = 0x05;
+
+/// Greeting sent by the client, offering only "no authentication" method,
+/// which is used by Tor daemon
+pub const SOCKS5_GREETING: [u8; 3] = [SOCKS5_VERSION, 1, METHOD_NO_AUTH];
+
+/// Size of the initial part of the reply to the `CONNECT` request which is
+/// sufficient to compute the full reply size with [`socks5_reply_len`]
+pub const SOCKS5_REPLY_HEAD_SIZE: usize = 5;
+
+const METHOD_NO_AUTH: u8 = 0x00;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// Errors reported by SOCKS5 proxy or happening during parsing of the proxy
+/// replies
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error
+)]
+#[display(doc_comments)]
+pub enum Error {
+    /// SOCKS5 reply is incomplete
+    IncompleteReply,
+
+    /// SOCKS5 reply has unsupported protocol version {0}
+    WrongVersion(u8),
+
+    /// SOCKS5 reply has unknown address type {0}
+    WrongAddrType(u8),
+
+    /// SOCKS5 proxy requires authentication (selected method {0:#04x}),
+    /// which is not supported
+    AuthRequired(u8),
+
+    /// general SOCKS5 server failure
+    GeneralFailure,
+
+    /// connection is not allowed by SOCKS5 proxy ruleset
+    NotAllowed,
+
+    /// network is unreachable by SOCKS5 proxy
+    NetworkUnreachable,
+
+    /// host is unreachable by SOCKS5 proxy
+    HostUnreachable,
+
+    /// connection was refused by the remote host
+    ConnectionRefused,
+
+    /// TTL expired while connecting via SOCKS5 proxy
+    TtlExpired,
+
+    /// SOCKS5 command is not supported by the proxy
+    CommandNotSupported,
+
+    /// address type is not supported by SOCKS5 proxy
+    AddrTypeNotSupported,
+
+    /// unknown SOCKS5 reply code {0}
+    UnknownReply(u8),
+}
+
+impl Error {
+    /// Constructs error from a non-zero SOCKS5 reply code
+    fn with_reply_code(code: u8) -> Error {
+        match code {
+            0x01 => Error::GeneralFailure,
+            0x02 => Error::NotAllowed,
+            0x03 => Error::NetworkUnreachable,
+            0x04 => Error::HostUnreachable,
+            0x05 => Error::ConnectionRefused,
+            0x06 => Error::TtlExpired,
+            0x07 => Error::CommandNotSupported,
+            0x08 => Error::AddrTypeNotSupported,
+            code => Error::UnknownReply(code),
+        }
+    }
+}
+
+/// Encodes SOCKS5 `CONNECT` request to the remote address and port. Onion
+/// addresses are encoded as domain names (`<base32>.onion`), which are
+/// resolved by the Tor daemon; IP addresses are encoded in binary form.
+pub fn encode_socks5_connect(addr: InetAddr, port: u16) -> Vec<u8> {
+    let mut request = vec![SOCKS5_VERSION, CMD_CONNECT, 0x00];
+    match addr {
+        InetAddr::IPv4(ip) => {
+            request.push(ATYP_IPV4);
+            request.extend(&ip.octets());
+        }
+        InetAddr::IPv6(ip) => {
+            request.push(ATYP_IPV6);
+            request.extend(&ip.octets());
+        }
+        // Onion addresses
+        _ => {
+            let domain = addr.to_string();
+            request.push(ATYP_DOMAIN);
+            // Onion v3 domain is always 62 bytes long, so it fits into u8
+            request.push(domain.len() as u8);
+            request.extend(domain.as_bytes());
+        }
+    }
+    request.extend(&port.to_be_bytes());
+    request
+}
+
+/// Parses proxy reply to the [`SOCKS5_GREETING`], checking that the proxy
+/// has accepted connection without authentication
+pub fn parse_socks5_method(reply: &[u8]) -> Result<(), Error> {
+    match reply {
+        [SOCKS5_VERSION, METHOD_NO_AUTH] => Ok(()),
+        [SOCKS5_VERSION, method] => Err(Error::AuthRequired(*method)),
+        [version, _] => Err(Error::WrongVersion(*version)),
+        _ => Err(Error::IncompleteReply),
+    }
+}
+
+/// Computes full size of the reply to the `CONNECT` request, including bound
+/// address and port, from its first [`SOCKS5_REPLY_HEAD_SIZE`] bytes
+pub fn socks5_reply_len(reply: &[u8]) -> Result<usize, Error> {
+    if reply.len() < 4 {
+        return Err(Error::IncompleteReply);
+    }
+    if reply[0] != SOCKS5_VERSION {
+        return Err(Error::WrongVersion(reply[0]));
+    }
+    let addr_len = match reply[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => {
+            1 + *reply.get(4).ok_or(Error::IncompleteReply)? as usize
+        }
+        atyp => return Err(Error::WrongAddrType(atyp)),
+    };
+    Ok(4 + addr_len + 2)
+}
+
+/// Parses SOCKS5 reply to the `CONNECT` request, checking that the
+/// connection was successfully established by the proxy. The reply must be
+/// complete, i.e. include bound address and port information.
+pub fn parse_socks5_reply(reply: &[u8]) -> Result<(), Error> {
+    if reply.len() < 4 {
+        return Err(Error::IncompleteReply);
+    }
+    if reply[0] != SOCKS5_VERSION {
+        return Err(Error::WrongVersion(reply[0]));
+    }
+    if reply[1] != 0x00 {
+        return Err(Error::with_reply_code(reply[1]));
+    }
+    if reply.len() < socks5_reply_len(reply)? {
+        return Err(Error::IncompleteReply);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv6Addr;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_connect() {
+        assert_eq!(
+            encode_socks5_connect(InetAddr::from([127, 0, 0, 1]), 9735),
+            vec![5, 1, 0, 1, 127, 0, 0, 1, 0x26, 0x07]
+        );
+
+        let mut expected = vec![5, 1, 0, 4];
+        expected.extend(&Ipv6Addr::LOCALHOST.octets());
+        expected.extend(&[0x26, 0x07]);
+        assert_eq!(
+            encode_socks5_connect(Ipv6Addr::LOCALHOST.into(), 9735),
+            expected
+        );
+
+        #[cfg(feature = "tor")]
+        {
+            let onion =
+                "p53lf57qovyuvwsc6xnrppyply3vtqm7l6pcobkmyqsiofyeznfu5uqd";
+            let request = encode_socks5_connect(onion.parse().unwrap(), 9735);
+            assert_eq!(request[..5], [5, 1, 0, 3, 62]);
+            assert_eq!(&request[5..67], format!("{}.onion", onion).as_bytes());
+            assert_eq!(request[67..], [0x26, 0x07]);
+        }
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_socks5_reply(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]), Ok(()));
+        let mut reply = vec![5, 0, 0, 4];
+        reply.extend(&[0u8; 18]);
+        assert_eq!(parse_socks5_reply(&reply), Ok(()));
+        assert_eq!(parse_socks5_reply(&[5, 0, 0, 3, 1, b'a', 0, 80]), Ok(()));
+    }
+
+    #[test]
+    fn test_method_selection() {
+        assert_eq!(SOCKS5_GREETING, [5, 1, 0]);
+        assert_eq!(parse_socks5_method(&[5, 0]), Ok(()));
+        assert_eq!(parse_socks5_method(&[5, 2]), Err(Error::AuthRequired(2)));
+        assert_eq!(
+            parse_socks5_method(&[5, 0xFF]),
+            Err(Error::AuthRequired(0xFF))
+        );
+        assert_eq!(parse_socks5_method(&[4, 0]), Err(Error::WrongVersion(4)));
+        assert_eq!(parse_socks5_method(&[5]), Err(Error::IncompleteReply));
+    }
+
+    #[test]
+    fn test_reply_len() {
+        assert_eq!(socks5_reply_len(&[5, 0, 0, 1, 0]), Ok(10));
+        assert_eq!(socks5_reply_len(&[5, 0, 0, 4, 0]), Ok(22));
+        assert_eq!(socks5_reply_len(&[5, 1, 0, 3, 62]), Ok(69));
+        assert_eq!(
+            socks5_reply_len(&[5, 0, 0, 2, 0]),
+            Err(Error::WrongAddrType(2))
+        );
+    }
+
+    #[test]
+    fn test_parse_reply_failures() {
+        let reply = |code| [5, code, 0, 1, 0, 0, 0, 0, 0, 0];
+        assert_eq!(parse_socks5_reply(&reply(1)), Err(Error::GeneralFailure));
+        assert_eq!(
+            parse_socks5_reply(&reply(4)),
+            Err(Error::HostUnreachable)
+        );
+        assert_eq!(
+            parse_socks5_reply(&reply(5)),
+            Err(Error::ConnectionRefused)
+        );
+        assert_eq!(parse_socks5_reply(&reply(6)), Err(Error::TtlExpired));
+        assert_eq!(
+            parse_socks5_reply(&reply(0x7F)),
+            Err(Error::UnknownReply(0x7F))
+        );
+
+        assert_eq!(
+            parse_socks5_reply(&[4, 0, 0, 1, 0, 0, 0, 0, 0, 0]),
+            Err(Error::WrongVersion(4))
+        );
+        assert_eq!(
+            parse_socks5_reply(&[5, 0, 0, 2, 0, 0, 0, 0, 0, 0]),
+            Err(Error::WrongAddrType(2))
+        );
+        assert_eq!(parse_socks5_reply(&[5, 0]), Err(Error::IncompleteReply));
+        assert_eq!(
+            parse_socks5_reply(&[5, 0, 0, 1, 0, 0, 0, 0, 0]),
+            Err(Error::IncompleteReply)
+        );
+        assert_eq!(
+            parse_socks5_reply(&[5, 0, 0, 3]),
+            Err(Error::IncompleteReply)
+        );
+    }
+}
@@ -17,6 +17,7 @@ use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::net::SocketAddr;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 
 use amplify::{Bipolar, Wrapper};
 #[cfg(feature = "serde")]
@@ -331,6 +332,186 @@ impl TryFrom<Url> for ZmqSocketAddr {
     }
 }
 
+/// Kind of the event reported by ZMQ socket monitor
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[non_exhaustive]
+pub enum ZmqEventKind {
+    /// Connection to the remote peer was established
+    #[display("connected")]
+    Connected,
+
+    /// Synchronous connect failed and is being retried asynchronously
+    #[display("connect delayed")]
+    ConnectDelayed,
+
+    /// Reconnection attempt was scheduled after a failed connection
+    #[display("connect retried")]
+    ConnectRetried,
+
+    /// Socket was bound to an endpoint and is ready to accept connections
+    #[display("listening")]
+    Listening,
+
+    /// Socket was unable to bind to an endpoint
+    #[display("bind failed")]
+    BindFailed,
+
+    /// Connection from a remote peer was accepted
+    #[display("accepted")]
+    Accepted,
+
+    /// Connection from a remote peer was rejected
+    #[display("accept failed")]
+    AcceptFailed,
+
+    /// Connection was closed
+    #[display("closed")]
+    Closed,
+
+    /// Connection was not closed due to an error
+    #[display("close failed")]
+    CloseFailed,
+
+    /// Session was broken unexpectedly
+    #[display("disconnected")]
+    Disconnected,
+
+    /// Monitoring of the socket was stopped
+    #[display("monitor stopped")]
+    MonitorStopped,
+
+    /// Security handshake with the remote peer has failed
+    #[display("handshake failed")]
+    HandshakeFailed,
+
+    /// Security handshake with the remote peer has succeeded
+    #[display("handshake succeeded")]
+    HandshakeSucceeded,
+
+    /// Event unknown to this library version
+    #[display("unknown event {0}")]
+    Unknown(u16),
+}
+
+impl From<u16> for ZmqEventKind {
+    fn from(raw: u16) -> Self {
+        use zmq::SocketEvent::*;
+        match raw {
+            x if x == CONNECTED.to_raw() => ZmqEventKind::Connected,
+            x if x == CONNECT_DELAYED.to_raw() => ZmqEventKind::ConnectDelayed,
+            x if x == CONNECT_RETRIED.to_raw() => ZmqEventKind::ConnectRetried,
+            x if x == LISTENING.to_raw() => ZmqEventKind::Listening,
+            x if x == BIND_FAILED.to_raw() => ZmqEventKind::BindFailed,
+            x if x == ACCEPTED.to_raw() => ZmqEventKind::Accepted,
+            x if x == ACCEPT_FAILED.to_raw() => ZmqEventKind::AcceptFailed,
+            x if x == CLOSED.to_raw() => ZmqEventKind::Closed,
+            x if x == CLOSE_FAILED.to_raw() => ZmqEventKind::CloseFailed,
+            x if x == DISCONNECTED.to_raw() => ZmqEventKind::Disconnected,
+            x if x == MONITOR_STOPPED.to_raw() => ZmqEventKind::MonitorStopped,
+            x if x == HANDSHAKE_FAILED_NO_DETAIL.to_raw()
+                || x == HANDSHAKE_FAILED_PROTOCOL.to_raw()
+                || x == HANDSHAKE_FAILED_AUTH.to_raw() =>
+            {
+                ZmqEventKind::HandshakeFailed
+            }
+            x if x == HANDSHAKE_SUCCEEDED.to_raw() => {
+                ZmqEventKind::HandshakeSucceeded
+            }
+            unknown => ZmqEventKind::Unknown(unknown),
+        }
+    }
+}
+
+/// Event reported by ZMQ socket monitor
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display("{kind} on {endpoint}")]
+pub struct ZmqEvent {
+    /// Kind of the event
+    pub kind: ZmqEventKind,
+
+    /// Event-specific value: file descriptor for connection-related events,
+    /// error number for failures or reconnection interval for
+    /// [`ZmqEventKind::ConnectRetried`]
+    pub value: u32,
+
+    /// Endpoint address the event relates to
+    pub endpoint: String,
+}
+
+/// Monitor for ZMQ socket connectivity events, created with
+/// [`Connection::monitor`] or [`WrappedSocket::monitor`].
+///
+/// Works as an iterator over the monitor events, which blocks until the next
+/// event arrives and stops after the monitoring is terminated (i.e. when the
+/// monitored socket gets closed).
+pub struct ZmqMonitor {
+    socket: zmq::Socket,
+    stopped: bool,
+}
+
+impl ZmqMonitor {
+    fn with(socket: &zmq::Socket) -> Result<Self, Error> {
+        static MONITOR_NO: AtomicUsize = AtomicUsize::new(0);
+        let endpoint = format!(
+            "inproc://internet2-zmq-monitor-{}",
+            MONITOR_NO.fetch_add(1, AtomicOrdering::Relaxed)
+        );
+        socket.monitor(&endpoint, zmq::SocketEvent::ALL.to_raw() as i32)?;
+        let monitor = ZMQ_CONTEXT.socket(zmq::PAIR)?;
+        monitor.connect(&endpoint)?;
+        Ok(ZmqMonitor {
+            socket: monitor,
+            stopped: false,
+        })
+    }
+
+    /// Sets timeout for receiving next event, in milliseconds; `-1` means
+    /// infinite wait
+    #[inline]
+    pub fn set_timeout(&self, timeout: i32) -> Result<(), Error> {
+        Ok(self.socket.set_rcvtimeo(timeout)?)
+    }
+
+    /// Receives next event from the monitor.
+    ///
+    /// # Errors
+    /// If no events were reported within the timeout set with
+    /// [`ZmqMonitor::set_timeout`], returns `EAGAIN` error. Returns `EPROTO`
+    /// error if the monitor message can't be parsed.
+    pub fn next_event(&mut self) -> Result<ZmqEvent, Error> {
+        let mut parts = self.socket.recv_multipart(0)?.into_iter();
+        let (header, endpoint) = match (parts.next(), parts.next()) {
+            (Some(header), Some(endpoint)) if header.len() == 6 => {
+                (header, endpoint)
+            }
+            _ => return Err(zmq::Error::EPROTO.into()),
+        };
+        let kind =
+            ZmqEventKind::from(u16::from_ne_bytes([header[0], header[1]]));
+        let value =
+            u32::from_ne_bytes([header[2], header[3], header[4], header[5]]);
+        if kind == ZmqEventKind::MonitorStopped {
+            self.stopped = true;
+        }
+        Ok(ZmqEvent {
+            kind,
+            value,
+            endpoint: String::from_utf8_lossy(&endpoint).into_owned(),
+        })
+    }
+}
+
+impl Iterator for ZmqMonitor {
+    type Item = Result<ZmqEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped {
+            return None;
+        }
+        Some(self.next_event())
+    }
+}
+
 pub struct WrappedSocket {
     api_type: ZmqType,
     socket: zmq::Socket,
@@ -411,6 +592,56 @@ impl Connection {
     pub fn set_identity(
         &mut self,
         identity: &impl AsRef<[u8]>,
+    ) -> Result<(), Error> {
+        self.reconnect_with(|socket| socket.set_identity(identity.as_ref()))
+    }
+
+    /// Sets high-water mark for the outbound messages of the input part of
+    /// the connection, i.e. the maximum number of messages queued for each
+    /// of the peers. Depending on the socket type, ZMQ blocks or drops
+    /// messages when the limit is reached. Zero means no limit.
+    ///
+    /// Since ZMQ applies the limit only to the connections established after
+    /// the option was set, the socket is re-created and re-connected (or
+    /// re-bound) to the remote address, so the connection must be created
+    /// with [`Connection::with`].
+    ///
+    /// # Errors
+    /// Returns `EINVAL` error for connections without remote address and
+    /// errors of re-creating the socket.
+    #[inline]
+    pub fn set_send_hwm(&mut self, hwm: i32) -> Result<(), Error> {
+        self.reconnect_with(|socket| socket.set_sndhwm(hwm))
+    }
+
+    /// Sets high-water mark for the inbound messages of the input part of
+    /// the connection. See [`Connection::set_send_hwm`] for the details.
+    #[inline]
+    pub fn set_recv_hwm(&mut self, hwm: i32) -> Result<(), Error> {
+        self.reconnect_with(|socket| socket.set_rcvhwm(hwm))
+    }
+
+    /// Returns high-water mark for the outbound messages of the input part of
+    /// the connection
+    #[inline]
+    pub fn send_hwm(&self) -> Result<i32, Error> {
+        self.as_socket().get_sndhwm().map_err(Error::from)
+    }
+
+    /// Returns high-water mark for the inbound messages of the input part of
+    /// the connection
+    #[inline]
+    pub fn recv_hwm(&self) -> Result<i32, Error> {
+        self.as_socket().get_rcvhwm().map_err(Error::from)
+    }
+
+    /// Re-creates socket of the input part of the connection, applying
+    /// `configure` to it before it gets bound or connected to the remote
+    /// address. Identity and high-water marks of the replaced socket are
+    /// preserved.
+    fn reconnect_with(
+        &mut self,
+        configure: impl FnOnce(&zmq::Socket) -> Result<(), zmq::Error>,
     ) -> Result<(), Error> {
         let addr = if let Some(addr) = &self.remote_addr {
             addr
@@ -418,24 +649,57 @@ impl Connection {
             return Err(Error::from(zmq::Error::EINVAL));
         };
         let socket = self.input.as_socket_mut();
+        let identity = socket.get_identity()?;
+        let send_hwm = socket.get_sndhwm()?;
+        let recv_hwm = socket.get_rcvhwm()?;
         let endpoint = addr.zmq_socket_string();
-        socket.disconnect(&endpoint)?;
-        *socket = ZMQ_CONTEXT.socket(self.api_type.socket_type())?;
-        socket
-            .set_identity(identity.as_ref())
-            .map_err(Error::from)?;
-        match self.api_type {
+        let bind = match self.api_type {
             ZmqType::Pull
             | ZmqType::Rep
             | ZmqType::Pub
-            | ZmqType::RouterBind => socket.bind(&endpoint)?,
+            | ZmqType::RouterBind => true,
             ZmqType::Push
             | ZmqType::Req
             | ZmqType::Sub
-            | ZmqType::RouterConnect => socket.connect(&endpoint)?,
+            | ZmqType::RouterConnect => false,
+        };
+        if !bind {
+            socket.disconnect(&endpoint)?;
+        }
+        *socket = ZMQ_CONTEXT.socket(self.api_type.socket_type())?;
+        if !identity.is_empty() {
+            socket.set_identity(&identity)?;
+        }
+        socket.set_sndhwm(send_hwm)?;
+        socket.set_rcvhwm(recv_hwm)?;
+        configure(socket)?;
+        if bind {
+            socket.bind(&endpoint)?;
+        } else {
+            socket.connect(&endpoint)?;
         }
         Ok(())
     }
+
+    /// Sets up ZMQ socket monitor for the input part of the connection,
+    /// reporting all connectivity events. See [`ZmqMonitor`] for the details.
+    #[inline]
+    pub fn monitor(&self) -> Result<ZmqMonitor, Error> {
+        self.input.monitor()
+    }
+
+    /// Sends already composed [`RoutedFrame`] using the output part of the
+    /// connection. See [`WrappedSocket::send_routed_frame`] for the details.
+    #[inline]
+    pub fn send_routed_frame(
+        &mut self,
+        frame: &RoutedFrame,
+    ) -> Result<usize, transport::Error> {
+        self.output
+            .as_mut()
+            .unwrap_or(&mut self.input)
+            .send_routed_frame(frame)
+    }
 }
 
 impl WrappedSocket {
@@ -451,6 +715,32 @@ impl WrappedSocket {
     pub(crate) fn as_socket_mut(&mut self) -> &mut zmq::Socket {
         &mut self.socket
     }
+
+    /// Sets up ZMQ socket monitor reporting all connectivity events for the
+    /// socket. The socket must be created within [`ZMQ_CONTEXT`].
+    #[inline]
+    pub fn monitor(&self) -> Result<ZmqMonitor, Error> {
+        ZmqMonitor::with(&self.socket)
+    }
+
+    /// Sends already composed [`RoutedFrame`] via ROUTER socket. The frame
+    /// destination is used as the route for the ROUTER envelope, while the
+    /// previous hop information of the frame is ignored.
+    ///
+    /// # Returns
+    /// In case of success, number of message bytes sent
+    ///
+    /// # Errors
+    /// * [`transport::Error::OversizedFrame`] if the frame message exceeds
+    ///   [`super::MAX_FRAME_SIZE`]
+    /// * [`transport::Error::Zmq`] if the underlying ZMQ socket fails
+    #[inline]
+    pub fn send_routed_frame(
+        &mut self,
+        frame: &RoutedFrame,
+    ) -> Result<usize, transport::Error> {
+        self.send_routed(&frame.src, &frame.dst, &frame.dst, &frame.msg)
+    }
 }
 
 impl Duplex for Connection {
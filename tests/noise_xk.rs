@@ -1,6 +1,18 @@
-use internet2::{Accept, Connect, LocalNode, RemoteNodeAddr};
+use std::time::Duration;
+
+use internet2::addr::InetSocketAddr;
+use internet2::transport::Error;
+use internet2::{
+    session, Accept, Connect, LocalNode, RemoteNodeAddr, RemoteSocketAddr,
+};
 use secp256k1::Secp256k1;
 
+/// Returns port assigned by the system, which is free at the moment
+fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
 #[test]
 fn main() {
     let secp = Secp256k1::new();
@@ -32,3 +44,61 @@ fn sender(local_node: &LocalNode, node: RemoteNodeAddr) {
     let mut session = node.connect(local_node).unwrap();
     session.send_raw_message(b"Hello world").unwrap();
 }
+
+#[test]
+fn handshake_key_mismatch() {
+    let secp = Secp256k1::new();
+    let node_rx = LocalNode::new(&secp);
+    let node_tx = LocalNode::new(&secp);
+    let wrong_node = LocalNode::new(&secp);
+    let node_addr: RemoteSocketAddr =
+        format!("lnp://127.0.0.1:{}", free_port()).parse().unwrap();
+    let node_a = RemoteNodeAddr {
+        node_id: node_rx.node_id(),
+        remote_addr: node_addr,
+    };
+    let node_b = RemoteNodeAddr {
+        node_id: wrong_node.node_id(),
+        remote_addr: node_addr,
+    };
+
+    let rx = std::thread::spawn(move || node_a.accept(&node_rx).err());
+    // Waiting for the listener to get ready
+    while let Err(Error::ConnectionRefused) = node_b.connect(&node_tx) {
+        std::thread::sleep(Duration::from_millis(10))
+    }
+
+    assert!(matches!(rx.join().unwrap(), Some(Error::HandshakeFailed(_))));
+}
+
+#[test]
+fn remote_static_keys() {
+    let secp = Secp256k1::new();
+    let node_rx = LocalNode::new(&secp);
+    let node_tx = LocalNode::new(&secp);
+    let rx_id = node_rx.node_id();
+    let tx_id = node_tx.node_id();
+    let inet_addr = InetSocketAddr::new([127, 0, 0, 1].into(), free_port());
+
+    let rx = std::thread::spawn(move || {
+        session::Raw::accept_ftcp_encrypted(node_rx.private_key(), inet_addr)
+            .unwrap()
+            .remote_static_key()
+    });
+    // Waiting for the listener to get ready
+    let session = loop {
+        match session::Raw::connect_ftcp_encrypted(
+            node_tx.private_key(),
+            rx_id,
+            inet_addr,
+        ) {
+            Err(Error::ConnectionRefused) => {
+                std::thread::sleep(Duration::from_millis(10))
+            }
+            res => break res.unwrap(),
+        }
+    };
+
+    assert_eq!(session.remote_static_key(), Some(rx_id.serialize()));
+    assert_eq!(rx.join().unwrap(), Some(tx_id.serialize()));
+}
@@ -1,4 +1,12 @@
-use internet2::{session, Session, ZmqSocketAddr, ZmqType};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use amplify::Bipolar;
+use internet2::transport::SendFrame;
+use internet2::zmqsocket::{Connection, ZmqEventKind};
+use internet2::{session, Duplex, Session, ZmqSocketAddr, ZmqType, ZMQ_CONTEXT};
 
 #[test]
 fn main() {
@@ -45,3 +53,108 @@ fn main() {
 
     tx.join().unwrap();
 }
+
+#[test]
+fn forward_routed_frame() {
+    let node_addr1: ZmqSocketAddr = "inproc://zmq-test-2".parse().unwrap();
+    let node_addr2 = node_addr1.clone();
+
+    let mut router =
+        Connection::with(ZmqType::RouterBind, &node_addr1, None, Some(b"rx"))
+            .unwrap();
+
+    let tx = std::thread::spawn(move || {
+        let mut conn = Connection::with(
+            ZmqType::RouterConnect,
+            &node_addr2,
+            None,
+            Some(b"tx"),
+        )
+        .unwrap();
+        conn.as_sender()
+            .send_routed(b"tx", b"rx", b"tx", b"echo")
+            .unwrap();
+        let frame = conn.as_receiver().recv_routed().unwrap();
+        assert_eq!(frame.hop, b"rx");
+        assert_eq!(frame.src, b"tx");
+        assert_eq!(frame.dst, b"tx");
+        assert_eq!(frame.msg, b"echo");
+    });
+
+    let frame = router.as_receiver().recv_routed().unwrap();
+    assert_eq!(frame.hop, b"tx");
+    assert_eq!(frame.dst, b"tx");
+    assert_eq!(router.send_routed_frame(&frame).unwrap(), 4);
+
+    tx.join().unwrap();
+}
+
+#[test]
+fn monitor_connect() {
+    let addr: ZmqSocketAddr = "tcp://127.0.0.1:48653".parse().unwrap();
+
+    // Connecting before the remote is listening, so the connection event can
+    // not happen before the monitor is set up
+    let conn =
+        Connection::with(ZmqType::RouterConnect, &addr, None, Some(b"tx"))
+            .unwrap();
+    let mut monitor = conn.monitor().unwrap();
+    monitor.set_timeout(5000).unwrap();
+
+    let _listener =
+        Connection::with(ZmqType::RouterBind, &addr, None, Some(b"rx"))
+            .unwrap();
+
+    let event = loop {
+        let event = monitor
+            .next_event()
+            .expect("no connection event within timeout");
+        if event.kind == ZmqEventKind::Connected {
+            break event;
+        }
+    };
+    assert_eq!(event.endpoint, "tcp://127.0.0.1:48653");
+}
+
+#[test]
+fn send_hwm_backpressure() {
+    const COUNT: usize = 100;
+
+    let remote: ZmqSocketAddr = "inproc://zmq-test-hwm".parse().unwrap();
+    let local: ZmqSocketAddr = "inproc://zmq-test-hwm-local".parse().unwrap();
+
+    // Peer which does not drain its queue until all messages are sent
+    let receiver = ZMQ_CONTEXT.socket(zmq::PULL).unwrap();
+    receiver.set_rcvhwm(1).unwrap();
+    receiver.bind(&remote.zmq_socket_string()).unwrap();
+
+    let mut conn =
+        Connection::with(ZmqType::Push, &remote, Some(local), None::<&[u8]>)
+            .unwrap();
+    conn.set_send_hwm(1).unwrap();
+    conn.set_recv_hwm(10).unwrap();
+    assert_eq!(conn.send_hwm(), Ok(1));
+    assert_eq!(conn.recv_hwm(), Ok(10));
+
+    let (mut push, _) = Bipolar::split(conn);
+    let sent = Arc::new(AtomicUsize::new(0));
+    let counter = sent.clone();
+    let tx = thread::spawn(move || {
+        for _ in 0..COUNT {
+            push.send_frame(b"message").unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    thread::sleep(Duration::from_millis(200));
+    assert!(
+        sent.load(Ordering::SeqCst) < COUNT,
+        "sending was not blocked by the high-water mark"
+    );
+
+    for _ in 0..COUNT {
+        assert_eq!(receiver.recv_bytes(0).unwrap(), b"message");
+    }
+    tx.join().unwrap();
+    assert_eq!(sent.load(Ordering::SeqCst), COUNT);
+}